@@ -13,7 +13,7 @@ use crate::square::Square;
 /// Macro for generating a bitboard. Performs no input validation. An invocation must look like:
 ///
 /// ```ignore
-/// # use hash_bootstrap::bb;
+/// # use mangrove_bootstrap::bb;
 ///
 /// bb!(
 ///     0bXXXXXXXX
@@ -46,6 +46,11 @@ macro_rules! bb {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ToTokenStream)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 /// A Bitboard is a 64-bit integer representing the 64 squares of a Chess board. Each bit of the
 /// integer is mapped to a Chess board square. The bits can represent anything, but typically
 /// represent certain predicates that hold true on squares with value `1`.
@@ -107,6 +112,11 @@ impl Iterator for BitIter {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 /// A color in Chess, either [`White`](Color::White) or [`Black`](Color::Black). Used in [`Piece`],
 /// [`Board`] and more.
 pub enum Color {
@@ -151,6 +161,62 @@ impl Display for Color {
     }
 }
 
+/// One of the eight directions pieces slide in, as seen from white's side of the board. Used by
+/// the set-wise fills of [`BitBoard`], such as [`BitBoard::occluded_fill`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction {
+    pub const ALL: [Self; 8] = [
+        Self::Up,
+        Self::UpRight,
+        Self::Right,
+        Self::DownRight,
+        Self::Down,
+        Self::DownLeft,
+        Self::Left,
+        Self::UpLeft,
+    ];
+
+    /// The directions a rook slides in.
+    pub const ORTHOGONAL: [Self; 4] = [Self::Up, Self::Right, Self::Down, Self::Left];
+
+    /// The directions a bishop slides in.
+    pub const DIAGONAL: [Self; 4] = [Self::UpRight, Self::DownRight, Self::DownLeft, Self::UpLeft];
+
+    /// Returns the direction pawns of the passed color push in.
+    pub fn up(color: Color) -> Self {
+        match color {
+            Color::White => Self::Up,
+            Color::Black => Self::Down,
+        }
+    }
+
+    // The number of bits a square moves by in this direction, along with the squares that can be
+    // reached by moving in it without wrapping around the edge files.
+    fn step(self) -> (i8, BitBoard) {
+        match self {
+            Self::Up => (8, BitBoard::FULL),
+            Self::UpRight => (9, !BitBoard::A_FILE),
+            Self::Right => (1, !BitBoard::A_FILE),
+            Self::DownRight => (-7, !BitBoard::A_FILE),
+            Self::Down => (-8, BitBoard::FULL),
+            Self::DownLeft => (-9, !BitBoard::H_FILE),
+            Self::Left => (-1, !BitBoard::H_FILE),
+            Self::UpLeft => (7, !BitBoard::H_FILE),
+        }
+    }
+}
+
 impl BitBoard {
     /// The empty bitboard, containing no `1`s.
     pub const EMPTY: Self = Self(0);
@@ -264,50 +330,20 @@ impl BitBoard {
         0b11111111
     );
 
-    // Used to check both if a piece attacks a spot between the king and rook and if the space
-    // between them is empty.
-    pub const WHITE_KING_SIDE_CASTLE_MASK: Self = bb!(
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000110
+    /// A bitboard containing `1`s for each light square, such as H1 and A8.
+    pub const LIGHT_SQUARES: Self = bb!(
+        0b10101010
+        0b01010101
+        0b10101010
+        0b01010101
+        0b10101010
+        0b01010101
+        0b10101010
+        0b01010101
     );
 
-    // Used to check if there are any pieces between the rook and king
-    pub const WHITE_QUEEN_SIDE_CASTLE_OCCUPATION_MASK: Self = bb!(
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b01110000
-    );
-
-    // Used to check if there are any attacks between the king and the king's final spot
-    pub const WHITE_QUEEN_SIDE_CASTLE_ATTACK_MASK: Self = bb!(
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00110000
-    );
-
-    pub const BLACK_KING_SIDE_CASTLE_MASK: Self = Self::WHITE_KING_SIDE_CASTLE_MASK.vertical_flip();
-
-    pub const BLACK_QUEEN_SIDE_CASTLE_ATTACK_MASK: Self =
-        Self::WHITE_QUEEN_SIDE_CASTLE_ATTACK_MASK.vertical_flip();
-
-    pub const BLACK_QUEEN_SIDE_CASTLE_OCCUPATION_MASK: Self =
-        Self::WHITE_QUEEN_SIDE_CASTLE_OCCUPATION_MASK.vertical_flip();
+    /// A bitboard containing `1`s for each dark square, such as A1 and H8.
+    pub const DARK_SQUARES: Self = Self(!Self::LIGHT_SQUARES.0);
 
     pub const PAWN_START_RANKS: Self = bb!(
         0b00000000
@@ -342,38 +378,6 @@ impl BitBoard {
         0b00000000
     );
 
-    pub const KING_CASTLE_MOVES: Self = bb!(
-        0b00101010
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00000000
-        0b00101010
-    );
-
-    pub fn king_side_castle_mask(color: Color) -> Self {
-        match color {
-            Color::White => Self::WHITE_KING_SIDE_CASTLE_MASK,
-            Color::Black => Self::BLACK_KING_SIDE_CASTLE_MASK,
-        }
-    }
-
-    pub fn queen_side_castle_occupation_mask(color: Color) -> Self {
-        match color {
-            Color::White => Self::WHITE_QUEEN_SIDE_CASTLE_OCCUPATION_MASK,
-            Color::Black => Self::BLACK_QUEEN_SIDE_CASTLE_OCCUPATION_MASK,
-        }
-    }
-
-    pub fn queen_side_castle_attack_mask(color: Color) -> Self {
-        match color {
-            Color::White => Self::WHITE_QUEEN_SIDE_CASTLE_ATTACK_MASK,
-            Color::Black => Self::BLACK_QUEEN_SIDE_CASTLE_ATTACK_MASK,
-        }
-    }
-
     /// Checks if the bitboard contains a single `1` bit.
     pub fn is_a_single_one(&self) -> bool {
         self.0.is_power_of_two()
@@ -395,7 +399,7 @@ impl BitBoard {
     ///
     /// # Example
     /// ```ignore
-    /// # use hash_bootstrap::BitBoard;
+    /// # use mangrove_bootstrap::BitBoard;
     ///
     /// let full = BitBoard::FULL;
     ///
@@ -418,7 +422,7 @@ impl BitBoard {
     ///
     /// # Example
     /// ```rust
-    /// # use hash_bootstrap::BitBoard;
+    /// # use mangrove_bootstrap::BitBoard;
     ///
     ///  // Ad infinitum!
     ///  for square in BitBoard::FULL.bits() {
@@ -534,7 +538,7 @@ impl BitBoard {
     ///
     /// # Example
     /// ```rust
-    /// # use hash_bootstrap::BitBoard;
+    /// # use mangrove_bootstrap::BitBoard;
     ///
     /// let bb = BitBoard::FULL;
     ///
@@ -548,7 +552,7 @@ impl BitBoard {
     ///
     /// # Example
     /// ```rust
-    /// # use hash_bootstrap::{BitBoard, Square};
+    /// # use mangrove_bootstrap::{BitBoard, Square};
     ///
     /// let bb = BitBoard::EMPTY;
     ///
@@ -562,7 +566,7 @@ impl BitBoard {
     ///
     /// # Example
     /// ```rust
-    /// # use hash_bootstrap::{BitBoard, Square};
+    /// # use mangrove_bootstrap::{BitBoard, Square};
     ///
     /// let mut bb = BitBoard::EMPTY;
     ///
@@ -600,6 +604,11 @@ impl BitBoard {
         Self(self.0.swap_bytes())
     }
 
+    /// Flips the bitboard horizontally, such that the A file becomes the H file and vice versa.
+    pub const fn horizontal_flip(self) -> Self {
+        Self(self.0.reverse_bits().swap_bytes())
+    }
+
     /// Smears all of the `1` bits of the bitboard one rank up relative to the context `color`.
     /// Equivalent to `bb.move_one_up(color) + bb`.
     ///
@@ -635,6 +644,165 @@ impl BitBoard {
     pub fn is_subset_of(&self, other: Self) -> bool {
         *self & other == *self
     }
+
+    fn shift_by(self, step: i8) -> Self {
+        if step >= 0 {
+            self.shift_visually_right(step as u8)
+        } else {
+            self.shift_visually_left(step.unsigned_abs())
+        }
+    }
+
+    /// Moves the bits in this bitboard one square in the passed direction. Bits moved out of the
+    /// board "disappear".
+    pub fn move_one(self, direction: Direction) -> Self {
+        let (step, reachable) = direction.step();
+
+        self.shift_by(step) & reachable
+    }
+
+    /// Fills the squares reached by sliding each `1` bit of the bitboard in the passed direction,
+    /// only passing through the squares set in `empty`. The result includes the bits themselves,
+    /// but not the squares blocking them. This uses a Kogge-Stone fill, so all the bits are slid
+    /// at once in three steps.
+    ///
+    /// For example, sliding up from the `X` with the blockers marked as `1`:
+    /// ```text
+    /// . . . . . . . .
+    /// . . . 1 . . . .
+    /// . . . . . . . .
+    /// . . . . . . . .
+    /// . . . X . . . .
+    /// . . . . . . . .
+    /// . . . . . . . .
+    /// . . . . . . . .
+    /// ```
+    ///
+    /// The result would be:
+    /// ```text
+    /// . . . . . . . .
+    /// . . . . . . . .
+    /// . . . 1 . . . .
+    /// . . . 1 . . . .
+    /// . . . 1 . . . .
+    /// . . . . . . . .
+    /// . . . . . . . .
+    /// . . . . . . . .
+    /// ```
+    pub fn occluded_fill(self, empty: Self, direction: Direction) -> Self {
+        let (step, reachable) = direction.step();
+        let mut filled = self;
+        let mut empty = empty & reachable;
+
+        for step in [step, step * 2, step * 4] {
+            filled |= empty & filled.shift_by(step);
+            empty = empty & empty.shift_by(step);
+        }
+
+        filled
+    }
+
+    /// Returns the squares attacked by sliding each `1` bit of the bitboard in the passed
+    /// direction, through the squares set in `empty`. Unlike [`BitBoard::occluded_fill`], this
+    /// includes the blocking squares and excludes the bits themselves, unless another bit attacks
+    /// them.
+    pub fn slide_attacks(self, empty: Self, direction: Direction) -> Self {
+        self.occluded_fill(empty, direction).move_one(direction)
+    }
+
+    // Combines the slide attacks of the bits in each of the passed directions.
+    fn slide_attacks_towards(self, empty: Self, directions: [Direction; 4]) -> Self {
+        directions
+            .into_iter()
+            .fold(Self::EMPTY, |attacks, direction| {
+                attacks | self.slide_attacks(empty, direction)
+            })
+    }
+
+    /// Returns every square attacked by at least one of the passed rooks, which can slide through
+    /// the squares set in `empty`.
+    pub fn rook_attacks_setwise(rooks: Self, empty: Self) -> Self {
+        rooks.slide_attacks_towards(empty, Direction::ORTHOGONAL)
+    }
+
+    /// Returns every square attacked by at least one of the passed bishops, which can slide
+    /// through the squares set in `empty`.
+    pub fn bishop_attacks_setwise(bishops: Self, empty: Self) -> Self {
+        bishops.slide_attacks_towards(empty, Direction::DIAGONAL)
+    }
+
+    /// Returns every square attacked by at least one of the passed queens, which can slide through
+    /// the squares set in `empty`.
+    pub fn queen_attacks_setwise(queens: Self, empty: Self) -> Self {
+        Self::rook_attacks_setwise(queens, empty) | Self::bishop_attacks_setwise(queens, empty)
+    }
+
+    /// Returns every square attacked by at least one of the passed knights.
+    pub fn knight_attacks_setwise(knights: Self) -> Self {
+        let top = knights.move_one_up(Color::White);
+        let bottom = knights.move_one_down(Color::White);
+        let left = knights.move_one_left(Color::White);
+        let right = knights.move_one_right(Color::White);
+
+        top.move_one_up_right(Color::White)
+            | top.move_one_up_left(Color::White)
+            | left.move_one_up_left(Color::White)
+            | left.move_one_down_left(Color::White)
+            | bottom.move_one_down_left(Color::White)
+            | bottom.move_one_down_right(Color::White)
+            | right.move_one_up_right(Color::White)
+            | right.move_one_down_right(Color::White)
+    }
+
+    /// Returns every square attacked by at least one of the passed kings.
+    pub fn king_attacks_setwise(kings: Self) -> Self {
+        let sideways = kings.move_one_left(Color::White) | kings.move_one_right(Color::White);
+        let line = sideways | kings;
+
+        line.move_one_up(Color::White) | sideways | line.move_one_down(Color::White)
+    }
+
+    /// Returns every square attacked by at least one of the passed pawns of the passed color.
+    pub fn pawn_attacks_setwise(pawns: Self, color: Color) -> Self {
+        pawns.move_one_up_left(color) | pawns.move_one_up_right(color)
+    }
+
+    /// Returns the squares in front of each `1` bit of the bitboard on its file, relative to the
+    /// passed color. For pawns, these are the squares they must pass through to promote.
+    pub fn front_span(self, color: Color) -> Self {
+        self.slide_attacks(Self::FULL, Direction::up(color))
+    }
+
+    /// Returns the squares behind each `1` bit of the bitboard on its file, relative to the passed
+    /// color.
+    pub fn rear_span(self, color: Color) -> Self {
+        self.front_span(!color)
+    }
+
+    /// Returns every square on the files of the `1` bits of the bitboard.
+    pub fn file_fill(self) -> Self {
+        self.occluded_fill(Self::FULL, Direction::Up)
+            | self.occluded_fill(Self::FULL, Direction::Down)
+    }
+
+    /// Returns every square on the files next to the files of the `1` bits of the bitboard. Files
+    /// with bits on them are included when they are next to another such file.
+    pub fn adjacent_files(self) -> Self {
+        let files = self.file_fill();
+
+        files.move_one_left(Color::White) | files.move_one_right(Color::White)
+    }
+
+    /// Returns the squares in front of each `1` bit of the bitboard, on its file and the files next
+    /// to it, relative to the passed color. A pawn is passed when no opposing pawn is on these
+    /// squares.
+    pub fn passed_pawn_mask(self, color: Color) -> Self {
+        let front_span = self.front_span(color);
+
+        front_span
+            | front_span.move_one_left(Color::White)
+            | front_span.move_one_right(Color::White)
+    }
 }
 
 impl Not for BitBoard {
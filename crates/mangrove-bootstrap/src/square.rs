@@ -218,7 +218,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::{Color, Square};
+    /// # use mangrove_bootstrap::{Color, Square};
     ///
     /// let square = Square::F4;
     ///
@@ -239,7 +239,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::{Color, Square};
+    /// # use mangrove_bootstrap::{Color, Square};
     ///
     /// let square = Square::F4;
     ///
@@ -260,7 +260,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::{Color, Square};
+    /// # use mangrove_bootstrap::{Color, Square};
     ///
     /// let square = Square::F4;
     ///
@@ -281,7 +281,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::{Color, Square};
+    /// # use mangrove_bootstrap::{Color, Square};
     ///
     /// let square = Square::F4;
     ///
@@ -302,7 +302,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::{Color, Square};
+    /// # use mangrove_bootstrap::{Color, Square};
     ///
     /// let square = Square::F4;
     ///
@@ -324,7 +324,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::Square;
+    /// # use mangrove_bootstrap::Square;
     ///
     /// let king = Square::WHITE_KING;
     ///
@@ -339,7 +339,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::Square;
+    /// # use mangrove_bootstrap::Square;
     ///
     /// let king = Square::BLACK_KING;
     ///
@@ -354,7 +354,7 @@ impl Square {
     ///
     /// # Example
     /// ```
-    /// # use hash_bootstrap::Square;
+    /// # use mangrove_bootstrap::Square;
     ///
     /// let square = Square::A1;
     ///
@@ -364,12 +364,50 @@ impl Square {
         self.0 as usize
     }
 
+    /// Flips the square vertically, such that the first rank becomes the eighth and vice versa.
+    ///
+    /// # Example
+    /// ```
+    /// # use mangrove_bootstrap::Square;
+    ///
+    /// assert_eq!(Square::E2.vertical_flip(), Square::E7);
+    /// ```
+    pub fn vertical_flip(&self) -> Self {
+        Self(self.0 ^ 56)
+    }
+
+    /// Flips the square horizontally, such that the A file becomes the H file and vice versa.
+    ///
+    /// # Example
+    /// ```
+    /// # use mangrove_bootstrap::Square;
+    ///
+    /// assert_eq!(Square::B3.horizontal_flip(), Square::G3);
+    /// ```
+    pub fn horizontal_flip(&self) -> Self {
+        Self(self.0 ^ 7)
+    }
+
+    /// Gets the square on the passed file and rank, which are numbered as returned from
+    /// [`Square::file`] and [`Square::rank`].
+    ///
+    /// # Panics
+    /// This function panics if either the file or the rank is larger than `7`.
+    pub fn from_file_and_rank(file: u8, rank: u8) -> Self {
+        assert!(
+            file < 8 && rank < 8,
+            "file and rank must be between 0 and 7"
+        );
+
+        Self(rank * 8 + file)
+    }
+
     /// Checks if assuming there is a line between the two squares `start` and `end`, `test` is on
     /// that line.
     ///
     /// # Example
     /// ```rust
-    /// # use hash_bootstrap::Square;
+    /// # use mangrove_bootstrap::Square;
     ///
     /// let start = Square::A1;
     /// let end = Square::H8;
@@ -427,7 +465,7 @@ impl Square {
     ///
     /// # Example
     /// ```rust
-    /// # use hash_bootstrap::Square;
+    /// # use mangrove_bootstrap::Square;
     ///
     /// let start = Square::C3;
     /// let end = Square::F6;
@@ -554,3 +592,29 @@ impl FromStr for Square {
         }
     }
 }
+
+/// Squares are serialized as their name, such as `e4`, in human-readable formats, and as their
+/// index otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Square {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(self.0)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Square {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        if deserializer.is_human_readable() {
+            Square::from_str(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+        } else {
+            Square::try_from(u8::deserialize(deserializer)?).map_err(D::Error::custom)
+        }
+    }
+}
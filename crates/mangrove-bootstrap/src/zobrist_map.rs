@@ -1,17 +1,16 @@
 use rustifact::ToTokenStream;
-use standard_dist::StandardDist;
 
-#[derive(Debug, StandardDist, ToTokenStream)]
+#[derive(Debug, ToTokenStream)]
 pub struct ZobristSide {
     pub white_to_move: u64,
     pub black_to_move: u64,
 }
 
-#[derive(Debug, StandardDist, ToTokenStream)]
+#[derive(Debug, ToTokenStream)]
 // This contains all configurations of castling rights for each player, as 2^4 = 16
 pub struct ZobristCastlingRights(pub [u64; 16]);
 
-#[derive(Debug, StandardDist, ToTokenStream)]
+#[derive(Debug, ToTokenStream)]
 pub struct ZobristPieces {
     pub king: [u64; 64],
     pub queen: [u64; 64],
@@ -21,7 +20,7 @@ pub struct ZobristPieces {
     pub pawn: [u64; 64],
 }
 
-#[derive(Debug, StandardDist, ToTokenStream)]
+#[derive(Debug, ToTokenStream)]
 pub struct ZobristMap {
     pub pieces: ZobristPieces,
     pub castling_rights: ZobristCastlingRights,
@@ -1,12 +1,35 @@
-use std::{array, io::Error};
+use std::{array, env, io::Error};
 
-use rand::{rngs::StdRng, Rng, SeedableRng};
-
-use mangrove_bootstrap::{BitBoard, Color, Metadata, Square, ZobristMap};
+use mangrove_bootstrap::{
+    BitBoard, Color, Metadata, Square, ZobristCastlingRights, ZobristMap, ZobristPieces,
+    ZobristSide,
+};
 use rustifact::ToTokenStream;
 
 const SEED: u64 = 0x73130172E6DEA605;
 
+// Overrides the seed the Zobrist keys are generated from, as a decimal or `0x` prefixed hex number.
+const ZOBRIST_SEED_VAR: &str = "MANGROVE_ZOBRIST_SEED";
+
+// The SplitMix64 generator, which is used instead of `rand`'s generators as its output is fully
+// specified, so the generated tables never change between builds, platforms or `rand` versions.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_array<const N: usize>(&mut self) -> [u64; N] {
+        array::from_fn(|_| self.next_u64())
+    }
+}
+
 fn gen_ray(
     pieces: BitBoard,
     blockers: BitBoard,
@@ -82,27 +105,6 @@ fn gen_bishop_slides(pieces: BitBoard, blockers: BitBoard) -> BitBoard {
     up_left | up_right | down_right | down_left
 }
 
-fn gen_knight_index(piece: BitBoard) -> BitBoard {
-    let top = piece.move_one_up(Color::White);
-    let bottom = piece.move_one_down(Color::White);
-    let left = piece.move_one_left(Color::White);
-    let right = piece.move_one_right(Color::White);
-
-    top.move_one_up_right(Color::White)
-        | top.move_one_up_left(Color::White)
-        | left.move_one_up_left(Color::White)
-        | left.move_one_down_left(Color::White)
-        | bottom.move_one_down_left(Color::White)
-        | bottom.move_one_down_right(Color::White)
-        | right.move_one_up_right(Color::White)
-        | right.move_one_down_right(Color::White)
-}
-
-fn gen_king_index(piece: BitBoard) -> BitBoard {
-    let line = piece.move_one_left(Color::White) | piece | piece.move_one_right(Color::White);
-    (line.move_one_up(Color::White) | line | line.move_one_down(Color::White)) & !piece
-}
-
 fn gen_rook_mask(piece: BitBoard) -> BitBoard {
     let (up, right, down, left) = gen_separated_cross_slides(piece, BitBoard::EMPTY);
     let correct_edges =
@@ -149,10 +151,10 @@ fn find_metadata(
     slide_fn: &impl Fn(BitBoard, BitBoard) -> BitBoard,
     offset: usize,
 ) -> (Metadata, Vec<BitBoard>) {
-    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut rng = SplitMix64(SEED);
 
     loop {
-        let [magic_1, magic_2, magic_3]: [u64; 3] = rng.gen();
+        let [magic_1, magic_2, magic_3] = rng.next_array();
         let mask = mask_fn(square.into());
 
         let metadata = Metadata {
@@ -185,6 +187,85 @@ fn generate_slides(
     (metadatas, slides)
 }
 
+// Gathers the bits of the value selected by the mask into the low bits of the result, as the PEXT
+// instruction does, so the tables can be generated on machines without it.
+fn pext(value: u64, mut mask: u64) -> usize {
+    let mut result = 0;
+    let mut bit = 1;
+
+    while mask != 0 {
+        let lowest = mask & mask.wrapping_neg();
+
+        if value & lowest != 0 {
+            result |= bit;
+        }
+
+        mask ^= lowest;
+        bit <<= 1;
+    }
+
+    result
+}
+
+// Generates the slides indexed with PEXT instead of magics. Each square's slides take as much space
+// as with its magic, so they start at the same offset.
+fn generate_pext_slides(
+    metadatas: &[Metadata],
+    slide_fn: &impl Fn(BitBoard, BitBoard) -> BitBoard,
+) -> Vec<BitBoard> {
+    let mut slides = Vec::new();
+
+    for (square, metadata) in Square::ALL.into_iter().zip(metadatas) {
+        assert_eq!(metadata.offset, slides.len());
+
+        let mut square_slides = vec![BitBoard::EMPTY; 1 << metadata.mask.count_ones()];
+
+        for subset in metadata.mask.subsets() {
+            square_slides[pext(subset.0, metadata.mask.0)] = slide_fn(square.into(), subset);
+        }
+
+        slides.append(&mut square_slides);
+    }
+
+    slides
+}
+
+fn zobrist_seed() -> u64 {
+    let Ok(seed) = env::var(ZOBRIST_SEED_VAR) else {
+        return SEED;
+    };
+
+    let parsed = match seed.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => seed.parse(),
+    };
+
+    parsed.unwrap_or_else(|_| panic!("{ZOBRIST_SEED_VAR} should be a 64-bit number, not {seed}"))
+}
+
+// The keys are drawn in the order of the fields, which must not change without bumping
+// `ZOBRIST_VERSION` in `board.rs`.
+fn gen_zobrist_map(seed: u64) -> ZobristMap {
+    let mut rng = SplitMix64(seed);
+
+    ZobristMap {
+        pieces: ZobristPieces {
+            king: rng.next_array(),
+            queen: rng.next_array(),
+            rook: rng.next_array(),
+            bishop: rng.next_array(),
+            knight: rng.next_array(),
+            pawn: rng.next_array(),
+        },
+        castling_rights: ZobristCastlingRights(rng.next_array()),
+        side: ZobristSide {
+            white_to_move: rng.next_u64(),
+            black_to_move: rng.next_u64(),
+        },
+        ep_file: rng.next_array(),
+    }
+}
+
 fn main() -> Result<(), Error> {
     let (rook_metadata, rook_slides) = generate_slides(&gen_rook_mask, &gen_rook_slides);
 
@@ -196,9 +277,31 @@ fn main() -> Result<(), Error> {
     rustifact::write_const_array!(BISHOP_SLIDE_METADATA, Metadata, &bishop_metadata);
     rustifact::write_const_array!(BISHOP_SLIDES, BitBoard, &bishop_slides);
 
-    rustifact::write_const_array!(KNIGHT_ATTACKS, BitBoard, &gen_piece_table(gen_knight_index));
+    // The PEXT slides are only looked up on x86-64, where the CPU is checked for BMI2 at runtime
+    if env::var("CARGO_CFG_TARGET_ARCH").is_ok_and(|arch| arch == "x86_64") {
+        rustifact::write_static_array!(
+            ROOK_PEXT_SLIDES,
+            BitBoard,
+            &generate_pext_slides(&rook_metadata, &gen_rook_slides)
+        );
+        rustifact::write_static_array!(
+            BISHOP_PEXT_SLIDES,
+            BitBoard,
+            &generate_pext_slides(&bishop_metadata, &gen_bishop_slides)
+        );
+    }
+
+    rustifact::write_const_array!(
+        KNIGHT_ATTACKS,
+        BitBoard,
+        &gen_piece_table(BitBoard::knight_attacks_setwise)
+    );
 
-    rustifact::write_const_array!(KING_ATTACKS, BitBoard, &gen_piece_table(gen_king_index));
+    rustifact::write_const_array!(
+        KING_ATTACKS,
+        BitBoard,
+        &gen_piece_table(BitBoard::king_attacks_setwise)
+    );
 
     rustifact::write_const_array!(
         WHITE_PAWN_ATTACKS,
@@ -286,13 +389,13 @@ fn main() -> Result<(), Error> {
         })
     );
 
-    rustifact::write_const!(
-        ZOBRIST_MAP,
-        ZobristMap,
-        StdRng::seed_from_u64(SEED).gen::<ZobristMap>()
-    );
+    let zobrist_seed = zobrist_seed();
+
+    rustifact::write_const!(ZOBRIST_SEED, u64, zobrist_seed);
+    rustifact::write_const!(ZOBRIST_MAP, ZobristMap, gen_zobrist_map(zobrist_seed));
 
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={ZOBRIST_SEED_VAR}");
 
     Ok(())
 }
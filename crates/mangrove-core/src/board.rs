@@ -10,10 +10,22 @@ use crate::{
     index,
     index::zobrist,
     mg,
-    repr::{ChessMove, ParsePieceBoardError, Piece, PieceBoard, PieceKind, PieceKindBoard, Player},
+    repr::{
+        back_rank, CastlingMode, CastlingSide, ChessMove, MoveKind, ParsePieceBoardError, Piece,
+        PieceBoard, PieceKind, PieceKindBoard, Player,
+    },
 };
 use mangrove_bootstrap::{BitBoard, Color, ParseSquareError, Square};
 
+/// The version of the way the Zobrist keys behind [`Board::hash`] are generated. Hashes are only
+/// comparable between builds with the same version and [`ZOBRIST_SEED`], so both should be stored
+/// along with persisted hashes.
+pub const ZOBRIST_VERSION: u32 = 1;
+
+/// The seed the Zobrist keys behind [`Board::hash`] are generated from. It's fixed unless the
+/// `MANGROVE_ZOBRIST_SEED` environment variable is set when building.
+pub const ZOBRIST_SEED: u64 = zobrist::SEED;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Board {
     pub us: Player,
@@ -26,54 +38,246 @@ pub struct Board {
     pub min_ply_clock: u8,
     pub full_moves: u16,
     pub hash: u64,
+    pub castling_mode: CastlingMode,
 }
 
 #[derive(Debug, thiserror::Error)]
 #[error("move is invalid for used board")]
 pub struct MakeMoveError;
 
+/// A cached part of a [`Board`] which is checked by [`Board::validate_invariants`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Invariant {
+    /// Each player has exactly one king.
+    KingCount,
+    /// No square holds more than one piece.
+    OverlappingPieces,
+    /// Each player's occupation is the union of its pieces.
+    Occupation,
+    /// The piece kind board matches the pieces of both players.
+    PieceKindBoard,
+    /// The checkers are the opposing pieces attacking the king of the side to move.
+    Checkers,
+    /// The pinned pieces are the pieces of the side to move which can't leave the line between
+    /// their king and an opposing slider.
+    Pinned,
+    /// Each castling right marks the square of its player's king or of one of its rooks, on the
+    /// player's back rank.
+    CastlingRights,
+    /// The hash is the Zobrist hash of the position.
+    Hash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("board breaks invariants: {broken:?}")]
+pub struct InvariantViolation {
+    pub broken: Vec<Invariant>,
+}
+
+/// The information lost when making a move on a board, which is needed to take it back using
+/// [`Board::unmake_move`]. It is returned by [`Board::make_move_unchecked`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveUndo {
+    captured_piece_kind: Option<PieceKind>,
+    castle: Option<Castle>,
+    origin_castling_right: bool,
+    target_castling_right: bool,
+    en_passant_capture_square: Option<Square>,
+    min_ply_clock: u8,
+    hash: u64,
+    checkers: BitBoard,
+    pinned: BitBoard,
+}
+
+// The squares involved in a castle, other than the king's starting square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Castle {
+    king_target: Square,
+    rook_origin: Square,
+    rook_target: Square,
+}
+
 impl Board {
     pub fn starting_position() -> Self {
         Self::from_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap()
     }
 
     pub fn is_attacked_by_them(&self, square: Square) -> bool {
+        self.is_attacked_by_them_with_occupation(square, self.occupation() & !self.us.king)
+    }
+
+    /// Like [`Board::is_attacked_by_them`], but sliding attacks are computed using the passed
+    /// occupation instead of the board's.
+    pub(crate) fn is_attacked_by_them_with_occupation(
+        &self,
+        square: Square,
+        occupation: BitBoard,
+    ) -> bool {
+        is_attacked_by(&self.them, !self.playing_color, square, occupation)
+    }
+
+    pub fn is_attacked_by_us(&self, square: Square) -> bool {
+        let occupation = self.occupation() & !self.them.king;
+
+        is_attacked_by(&self.us, self.playing_color, square, occupation)
+    }
+
+    /// Returns the pieces of both colors attacking the passed square, with sliding attacks
+    /// computed using the passed occupation instead of the board's. Pieces missing from the
+    /// occupation are never returned, so removing a piece from it reveals the x-ray attackers
+    /// behind it. The result can be intersected with a player's occupation to get the attackers of
+    /// a single color.
+    pub fn attackers_to(&self, square: Square, occupation: BitBoard) -> BitBoard {
+        let queens = self.us.queens | self.them.queens;
+        let rooks = self.us.rooks | self.them.rooks;
+        let bishops = self.us.bishops | self.them.bishops;
+
         let mut attackers = BitBoard::EMPTY;
-        let occupation = self.occupation() & !self.us.king;
 
-        attackers |= index::rook_slides(square, occupation) & (self.them.rooks | self.them.queens);
-        attackers |=
-            index::bishop_slides(square, occupation) & (self.them.bishops | self.them.queens);
+        attackers |= index::rook_slides(square, occupation) & (rooks | queens);
+        attackers |= index::bishop_slides(square, occupation) & (bishops | queens);
+
+        attackers |= index::knight_attacks(square) & (self.us.knights | self.them.knights);
+        attackers |= index::king_attacks(square) & (self.us.king | self.them.king);
 
-        attackers |= index::knight_attacks(square) & self.them.knights;
-        attackers |= index::king_attacks(square) & self.them.king;
+        // A pawn attacks the square exactly when a pawn of the other color on the square would
+        // attack the pawn
+        attackers |= index::pawn_attacks(square, Color::White) & self.player(Color::Black).pawns;
+        attackers |= index::pawn_attacks(square, Color::Black) & self.player(Color::White).pawns;
 
-        let square: BitBoard = square.into();
+        attackers & occupation
+    }
+
+    /// Returns all of the squares attacked by the pieces of the passed color, including squares
+    /// occupied by pieces of the same color.
+    pub fn attacked_squares(&self, color: Color) -> BitBoard {
+        let player = self.player(color);
+        let occupation = self.occupation();
+
+        let mut attacked = BitBoard::EMPTY;
+
+        for square in (player.rooks | player.queens).bits() {
+            attacked |= index::rook_slides(square, occupation);
+        }
+
+        for square in (player.bishops | player.queens).bits() {
+            attacked |= index::bishop_slides(square, occupation);
+        }
+
+        for square in player.knights.bits() {
+            attacked |= index::knight_attacks(square);
+        }
 
-        attackers |= (square.move_one_up_left(self.playing_color)
-            | square.move_one_up_right(self.playing_color))
-            & self.them.pawns;
+        for square in player.king.bits() {
+            attacked |= index::king_attacks(square);
+        }
 
-        !attackers.is_empty()
+        attacked | player.pawns.move_one_up_left(color) | player.pawns.move_one_up_right(color)
     }
 
-    pub fn is_attacked_by_us(&self, square: Square) -> bool {
-        let mut attackers = BitBoard::EMPTY;
-        let occupation = self.occupation() & !self.them.king;
+    /// Returns the player of the passed color.
+    pub fn player(&self, color: Color) -> &Player {
+        if color == self.playing_color {
+            &self.us
+        } else {
+            &self.them
+        }
+    }
 
-        attackers |= index::rook_slides(square, occupation) & (self.us.rooks | self.us.queens);
-        attackers |= index::bishop_slides(square, occupation) & (self.us.bishops | self.us.queens);
+    /// Returns the side the passed move castles on, if the move is a castle by the side to move.
+    /// Castles are recognized both when encoded as the king moving two squares and when encoded as
+    /// the king moving onto its castling rook, regardless of the board's castling mode.
+    pub fn castling_side(&self, chess_move: ChessMove) -> Option<CastlingSide> {
+        let is_castle = self.us.king.get_bit(chess_move.origin)
+            && chess_move.origin.rank() == chess_move.target.rank()
+            && (self.us.rooks.get_bit(chess_move.target)
+                || chess_move.origin.file().abs_diff(chess_move.target.file()) == 2);
+
+        is_castle.then(|| {
+            if chess_move.target.file() > chess_move.origin.file() {
+                CastlingSide::KingSide
+            } else {
+                CastlingSide::QueenSide
+            }
+        })
+    }
 
-        attackers |= index::knight_attacks(square) & self.us.knights;
-        attackers |= index::king_attacks(square) & self.us.king;
+    /// Finds what the passed move does on the board. The move is assumed to be legal for this
+    /// board.
+    pub fn classify(&self, chess_move: ChessMove) -> MoveKind {
+        if let Some(side) = self.castling_side(chess_move) {
+            return MoveKind::Castle(side);
+        }
 
-        let square: BitBoard = square.into();
+        let is_capture = self.them.occupation.get_bit(chess_move.target);
+
+        match (chess_move.promotion, is_capture) {
+            (Some(promotion), true) => MoveKind::CapturePromotion(promotion),
+            (Some(promotion), false) => MoveKind::Promotion(promotion),
+            (None, true) => MoveKind::Capture,
+            (None, false)
+                if self.piece_kind_board[chess_move.origin] == Some(PieceKind::Pawn)
+                    && Some(chess_move.target) == self.en_passant_capture_square =>
+            {
+                MoveKind::EnPassant
+            }
+            (None, false) => MoveKind::Quiet,
+        }
+    }
 
-        attackers |= (square.move_one_up_left(!self.playing_color)
-            | square.move_one_up_right(!self.playing_color))
-            & self.us.pawns;
+    /// Returns whether the passed move, assumed to be legal, captures a piece. Castles encoded as
+    /// the king moving onto its rook are not captures.
+    pub fn is_capture(&self, chess_move: ChessMove) -> bool {
+        self.classify(chess_move).is_capture()
+    }
+
+    /// Returns the piece the passed move, assumed to be legal, captures, which for en passant
+    /// isn't on the target square.
+    pub fn captured_piece(&self, chess_move: ChessMove) -> Option<Piece> {
+        match self.classify(chess_move) {
+            MoveKind::EnPassant => Some(Piece {
+                kind: PieceKind::Pawn,
+                color: !self.playing_color,
+            }),
+            kind if kind.is_capture() => self.piece(chess_move.target),
+            _ => None,
+        }
+    }
+
+    /// Returns the piece on the origin of the passed move, which for castles is the king.
+    pub fn moved_piece(&self, chess_move: ChessMove) -> Option<Piece> {
+        self.piece(chess_move.origin)
+    }
+
+    /// Returns whether the passed move, assumed to be legal, puts the opponent in check. This
+    /// doesn't make the move, so it's cheaper than checking the board after making it.
+    pub fn gives_check(&self, chess_move: ChessMove) -> bool {
+        mg::gives_check(self, chess_move)
+    }
+
+    /// Encodes a castle by the side to move with the passed rook, according to the board's
+    /// castling mode.
+    pub(crate) fn castling_move(&self, side: CastlingSide, rook_square: Square) -> ChessMove {
+        // SAFETY: The board is assumed to be validly constructed
+        let king_square = unsafe { Square::try_from(self.us.king).unwrap_unchecked() };
+        let king_target = side.king_target(self.playing_color);
+
+        // Chess960 positions where the king doesn't move two squares cannot use the standard
+        // encoding, so they fall back to the Chess960 one
+        let target = if self.castling_mode == CastlingMode::Standard
+            && king_square.file().abs_diff(king_target.file()) == 2
+        {
+            king_target
+        } else {
+            rook_square
+        };
 
-        !attackers.is_empty()
+        ChessMove {
+            origin: king_square,
+            target,
+            promotion: None,
+        }
     }
 
     pub fn in_check(&self) -> bool {
@@ -98,6 +302,7 @@ impl Board {
     // INVARIANT: A piece as specified must NOT exist on the specified square.
     unsafe fn add_piece_unchecked(&mut self, square: Square, piece: Piece) {
         self.piece_kind_board[square] = Some(piece.kind);
+        self.hash ^= zobrist::piece(piece, square);
 
         if piece.color == self.playing_color {
             &mut self.us
@@ -110,6 +315,7 @@ impl Board {
     // INVARIANT: A piece as specified must exist on the specified square.
     unsafe fn remove_piece_unchecked(&mut self, square: Square, piece: Piece) {
         self.piece_kind_board[square] = None;
+        self.hash ^= zobrist::piece(piece, square);
 
         if piece.color == self.playing_color {
             &mut self.us
@@ -143,8 +349,34 @@ impl Board {
         }
     }
 
-    // INVARIANT: The passed move must be legal in relation to the current board.
-    unsafe fn make_move_unchecked(&mut self, chess_move: ChessMove) {
+    /// Makes the passed move on the board without checking whether it is legal, returning the
+    /// information needed to take it back using [`Board::unmake_move`].
+    ///
+    /// # Safety
+    /// The passed move must be legal in relation to the current board.
+    pub unsafe fn make_move_unchecked(&mut self, chess_move: ChessMove) -> MoveUndo {
+        let castle = self.castling_side(chess_move).map(|side| Castle {
+            king_target: side.king_target(self.playing_color),
+            // SAFETY: The move is assumed to be legal, so castling on this side is allowed
+            rook_origin: unsafe { self.us.castling_rook(side).unwrap_unchecked() },
+            rook_target: side.rook_target(self.playing_color),
+        });
+
+        let mut undo = MoveUndo {
+            captured_piece_kind: match castle {
+                Some(_) => None,
+                None => self.piece_kind_board[chess_move.target],
+            },
+            castle,
+            origin_castling_right: self.us.castling_rights[chess_move.origin],
+            target_castling_right: self.them.castling_rights[chess_move.target],
+            en_passant_capture_square: self.en_passant_capture_square,
+            min_ply_clock: self.min_ply_clock,
+            hash: self.hash,
+            checkers: self.checkers,
+            pinned: self.pinned,
+        };
+
         self.en_passant_capture_square = None;
         self.checkers = BitBoard::EMPTY;
         self.pinned = BitBoard::EMPTY;
@@ -152,104 +384,126 @@ impl Board {
         // SAFETY: The board is assumed to be valid
         let enemy_king_square = Square::try_from(self.them.king).unwrap();
         let moved_piece_kind = self.piece_kind_board[chess_move.origin].unwrap();
-        let target_piece_kind = self.piece_kind_board[chess_move.target];
+        let target_piece_kind = undo.captured_piece_kind;
 
         let mut is_capture = false;
 
+        // The castling rights and en passant file are hashed out here and hashed back in once
+        // the move has been made.
+        self.hash ^= zobrist::castling_rights(&self.us, self.playing_color)
+            ^ zobrist::castling_rights(&self.them, !self.playing_color)
+            ^ en_passant_hash(
+                undo.en_passant_capture_square,
+                self.playing_color,
+                self.us.pawns,
+            );
+
         self.us.castling_rights[chess_move.origin] = false;
         self.them.castling_rights[chess_move.target] = false;
 
+        // The castling rook's right is taken away as well, as the king may later move onto its
+        // square, which must then not be considered to have castling rights
+        if let Some(castle) = castle {
+            self.us.castling_rights[castle.rook_origin] = false;
+        }
+
         // SAFETY: Move is assumed to be legal.
-        unsafe {
-            self.remove_piece_unchecked(
-                chess_move.origin,
-                Piece {
-                    kind: moved_piece_kind,
-                    color: self.playing_color,
-                },
-            );
+        if let Some(castle) = castle {
+            // The king and rook are both removed before being placed again, as in Chess960 their
+            // origin and target squares may overlap
+            unsafe {
+                self.remove_piece_unchecked(
+                    chess_move.origin,
+                    Piece {
+                        kind: PieceKind::King,
+                        color: self.playing_color,
+                    },
+                );
 
-            // Handle removing the captured piece
-            if let Some(target_piece_kind) = target_piece_kind {
                 self.remove_piece_unchecked(
-                    chess_move.target,
+                    castle.rook_origin,
                     Piece {
-                        kind: target_piece_kind,
-                        color: !self.playing_color,
+                        kind: PieceKind::Rook,
+                        color: self.playing_color,
                     },
                 );
 
-                is_capture = true;
-            } else if moved_piece_kind == PieceKind::Pawn {
-                if chess_move.origin.rank().abs_diff(chess_move.target.rank()) == 2 {
-                    // This must mean the move was a double-push
-                    self.en_passant_capture_square = Some(
-                        chess_move
-                            .target
-                            .move_one_down_unchecked(self.playing_color),
-                    )
-                } else if chess_move.origin.file() != chess_move.target.file() {
-                    // If we are here, this must mean the move was an en passant.
-                    self.remove_piece_unchecked(
-                        chess_move
-                            .target
-                            .move_one_down_unchecked(self.playing_color),
-                        Piece {
-                            kind: PieceKind::Pawn,
-                            color: !self.playing_color,
-                        },
-                    );
+                self.add_piece_unchecked(
+                    castle.king_target,
+                    Piece {
+                        kind: PieceKind::King,
+                        color: self.playing_color,
+                    },
+                );
 
-                    is_capture = true;
-                }
+                self.add_piece_unchecked(
+                    castle.rook_target,
+                    Piece {
+                        kind: PieceKind::Rook,
+                        color: self.playing_color,
+                    },
+                );
             }
-
-            self.add_piece_unchecked(
-                chess_move.target,
-                Piece {
-                    kind: chess_move.promotion.unwrap_or(moved_piece_kind),
-                    color: self.playing_color,
-                },
-            );
-        }
-
-        let move_bitboard = BitBoard::from(chess_move.origin) ^ chess_move.target.into();
-
-        // TODO: Check if replacing this with a more rudimentary check would be faster
-        if moved_piece_kind == PieceKind::King
-            && move_bitboard.is_subset_of(BitBoard::KING_CASTLE_MOVES)
-        {
-            // This must mean the move was a castle.
-            let king_square = chess_move.origin.as_index() as u8;
-
-            let (origin, target) = if chess_move.target.file() == Square::G_FILE {
-                (king_square + 3, king_square + 1)
-            } else {
-                (king_square - 4, king_square - 1)
-            };
-
-            // SAFETY: Move is assumed to be legal.
+        } else {
             unsafe {
                 self.remove_piece_unchecked(
-                    Square::try_from(origin).unwrap_unchecked(),
+                    chess_move.origin,
                     Piece {
-                        kind: PieceKind::Rook,
+                        kind: moved_piece_kind,
                         color: self.playing_color,
                     },
                 );
 
+                // Handle removing the captured piece
+                if let Some(target_piece_kind) = target_piece_kind {
+                    self.remove_piece_unchecked(
+                        chess_move.target,
+                        Piece {
+                            kind: target_piece_kind,
+                            color: !self.playing_color,
+                        },
+                    );
+
+                    is_capture = true;
+                } else if moved_piece_kind == PieceKind::Pawn {
+                    if chess_move.origin.rank().abs_diff(chess_move.target.rank()) == 2 {
+                        // This must mean the move was a double-push
+                        self.en_passant_capture_square = Some(
+                            chess_move
+                                .target
+                                .move_one_down_unchecked(self.playing_color),
+                        )
+                    } else if chess_move.origin.file() != chess_move.target.file() {
+                        // If we are here, this must mean the move was an en passant.
+                        self.remove_piece_unchecked(
+                            chess_move
+                                .target
+                                .move_one_down_unchecked(self.playing_color),
+                            Piece {
+                                kind: PieceKind::Pawn,
+                                color: !self.playing_color,
+                            },
+                        );
+
+                        undo.captured_piece_kind = Some(PieceKind::Pawn);
+                        is_capture = true;
+                    }
+                }
+
                 self.add_piece_unchecked(
-                    Square::try_from(target).unwrap_unchecked(),
+                    chess_move.target,
                     Piece {
-                        kind: PieceKind::Rook,
+                        kind: chess_move.promotion.unwrap_or(moved_piece_kind),
                         color: self.playing_color,
                     },
                 );
             }
         }
 
+        let moved_piece_square = castle.map_or(chess_move.target, |castle| castle.king_target);
+
         // Update `checkers` for the non-sliding pieces
-        self.checkers ^= match self.piece_kind_board[chess_move.target].unwrap() {
+        self.checkers ^= match self.piece_kind_board[moved_piece_square].unwrap() {
             PieceKind::Knight => index::knight_attacks(enemy_king_square) & self.us.knights,
             PieceKind::Pawn => {
                 index::pawn_attacks(enemy_king_square, !self.playing_color) & self.us.pawns
@@ -275,6 +529,16 @@ impl Board {
             }
         }
 
+        self.hash ^= zobrist::castling_rights(&self.us, self.playing_color)
+            ^ zobrist::castling_rights(&self.them, !self.playing_color)
+            ^ en_passant_hash(
+                self.en_passant_capture_square,
+                !self.playing_color,
+                self.them.pawns,
+            )
+            ^ zobrist::side(self.playing_color)
+            ^ zobrist::side(!self.playing_color);
+
         mem::swap(&mut self.us, &mut self.them);
 
         self.full_moves += (self.playing_color == Color::Black) as u16;
@@ -286,11 +550,235 @@ impl Board {
         };
 
         self.playing_color = !self.playing_color;
+
+        #[cfg(feature = "validate-invariants")]
+        self.assert_invariants(chess_move);
+
+        undo
+    }
+
+    /// Takes back a move made using [`Board::make_move_unchecked`], restoring the board to the
+    /// exact state it was in before the move was made.
+    ///
+    /// # Safety
+    /// The passed move must be the last move made on the board, and the passed undo record must
+    /// be the one returned when making it.
+    pub unsafe fn unmake_move(&mut self, chess_move: ChessMove, undo: MoveUndo) {
+        self.playing_color = !self.playing_color;
+        self.full_moves -= (self.playing_color == Color::Black) as u16;
+
+        mem::swap(&mut self.us, &mut self.them);
+
+        // SAFETY: The move is assumed to have been made on this board
+        unsafe {
+            if let Some(castle) = undo.castle {
+                self.remove_piece_unchecked(
+                    castle.king_target,
+                    Piece {
+                        kind: PieceKind::King,
+                        color: self.playing_color,
+                    },
+                );
+
+                self.remove_piece_unchecked(
+                    castle.rook_target,
+                    Piece {
+                        kind: PieceKind::Rook,
+                        color: self.playing_color,
+                    },
+                );
+
+                self.add_piece_unchecked(
+                    chess_move.origin,
+                    Piece {
+                        kind: PieceKind::King,
+                        color: self.playing_color,
+                    },
+                );
+
+                self.add_piece_unchecked(
+                    castle.rook_origin,
+                    Piece {
+                        kind: PieceKind::Rook,
+                        color: self.playing_color,
+                    },
+                );
+            } else {
+                // The target is occupied by the moved piece, as the move wasn't a castle
+                let moved_piece_kind = match chess_move.promotion {
+                    Some(_) => PieceKind::Pawn,
+                    None => self.piece_kind_board[chess_move.target].unwrap_unchecked(),
+                };
+
+                self.remove_piece_unchecked(
+                    chess_move.target,
+                    Piece {
+                        kind: chess_move.promotion.unwrap_or(moved_piece_kind),
+                        color: self.playing_color,
+                    },
+                );
+
+                self.add_piece_unchecked(
+                    chess_move.origin,
+                    Piece {
+                        kind: moved_piece_kind,
+                        color: self.playing_color,
+                    },
+                );
+
+                if let Some(captured_piece_kind) = undo.captured_piece_kind {
+                    // A pawn moving to the en passant capture square is always an en passant, in
+                    // which case the captured pawn isn't on the target square.
+                    let capture_square = if moved_piece_kind == PieceKind::Pawn
+                        && undo.en_passant_capture_square == Some(chess_move.target)
+                    {
+                        chess_move
+                            .target
+                            .move_one_down_unchecked(self.playing_color)
+                    } else {
+                        chess_move.target
+                    };
+
+                    self.add_piece_unchecked(
+                        capture_square,
+                        Piece {
+                            kind: captured_piece_kind,
+                            color: !self.playing_color,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Castling requires the rook to have castling rights, so they can be restored without
+        // being stored
+        if let Some(castle) = undo.castle {
+            self.us.castling_rights[castle.rook_origin] = true;
+        }
+
+        self.us.castling_rights[chess_move.origin] = undo.origin_castling_right;
+        self.them.castling_rights[chess_move.target] = undo.target_castling_right;
+        self.en_passant_capture_square = undo.en_passant_capture_square;
+        self.min_ply_clock = undo.min_ply_clock;
+        self.hash = undo.hash;
+        self.checkers = undo.checkers;
+        self.pinned = undo.pinned;
+
+        #[cfg(feature = "validate-invariants")]
+        self.assert_invariants(chess_move);
+    }
+
+    /// Checks whether the passed move follows the movement rules of the piece on its origin,
+    /// without checking whether it leaves the king in check. The move may come from any source,
+    /// including ones which know nothing about this board.
+    ///
+    /// Castles are fully validated, including whether the king passes through an attacked
+    /// square, and must be encoded according to the board's castling mode.
+    pub fn is_pseudo_legal(&self, chess_move: ChessMove) -> bool {
+        if !self.us.occupation.get_bit(chess_move.origin) {
+            return false;
+        }
+
+        if let Some(side) = self.castling_side(chess_move) {
+            return !self.in_check()
+                && self.us.castling_rook(side).is_some_and(|rook_square| {
+                    chess_move == self.castling_move(side, rook_square)
+                        && mg::King::is_legal_castle(self, side, rook_square)
+                });
+        }
+
+        if self.us.occupation.get_bit(chess_move.target) {
+            return false;
+        }
+
+        let occupation = self.occupation();
+        let origin = chess_move.origin;
+
+        let (targets, is_promotion) = match self.piece_kind_board[origin] {
+            Some(PieceKind::Pawn) => {
+                let mut targets = index::pawn_moves(
+                    origin,
+                    self.us.occupation & !BitBoard::from(origin),
+                    self.them.occupation,
+                    self.playing_color,
+                );
+
+                if let Some(en_passant_capture_square) = self.en_passant_capture_square {
+                    targets |= index::pawn_attacks(origin, self.playing_color)
+                        & BitBoard::from(en_passant_capture_square);
+                }
+
+                (targets, BitBoard::EDGE_RANKS.get_bit(chess_move.target))
+            }
+            Some(PieceKind::Knight) => (index::knight_attacks(origin), false),
+            Some(PieceKind::Bishop) => (index::bishop_slides(origin, occupation), false),
+            Some(PieceKind::Rook) => (index::rook_slides(origin, occupation), false),
+            Some(PieceKind::Queen) => (
+                index::rook_slides(origin, occupation) | index::bishop_slides(origin, occupation),
+                false,
+            ),
+            Some(PieceKind::King) => (index::king_attacks(origin), false),
+            None => return false,
+        };
+
+        let has_valid_promotion = match chess_move.promotion {
+            Some(kind) => is_promotion && PieceKind::PROMOTIONS.contains(&kind),
+            None => !is_promotion,
+        };
+
+        targets.get_bit(chess_move.target) && has_valid_promotion
+    }
+
+    /// Checks whether the passed move is legal for this board, without generating every legal
+    /// move. Like [`Board::is_pseudo_legal`], the move may come from any source.
+    pub fn is_legal(&self, chess_move: ChessMove) -> bool {
+        if !self.is_pseudo_legal(chess_move) {
+            return false;
+        }
+
+        // Castles are fully validated as pseudo-legal moves
+        if self.castling_side(chess_move).is_some() {
+            return true;
+        }
+
+        // SAFETY: The board is assumed to be validly constructed
+        let king_square = unsafe { Square::try_from(self.us.king).unwrap_unchecked() };
+
+        if chess_move.origin == king_square {
+            return !self.is_attacked_by_them(chess_move.target);
+        }
+
+        if self.checkers.count_ones() > 1 {
+            return false;
+        }
+
+        let is_en_passant = self.piece_kind_board[chess_move.origin] == Some(PieceKind::Pawn)
+            && Some(chess_move.target) == self.en_passant_capture_square;
+
+        if is_en_passant {
+            // A fresh en passant square means the only possible checks are by the pawn which
+            // just moved, which is captured, or by a slider it uncovered, which is covered here
+            // SAFETY: The move was checked to be a pseudo-legal en passant capture
+            return unsafe {
+                mg::Pawn::is_legal_en_passant_capture(self, chess_move.target, chess_move.origin)
+            };
+        }
+
+        if let Ok(checker) = Square::try_from(self.checkers) {
+            let blocking_squares = self.checkers | index::line_between(checker, king_square);
+
+            if !blocking_squares.get_bit(chess_move.target) {
+                return false;
+            }
+        }
+
+        !self.pinned.get_bit(chess_move.origin)
+            || index::line_fit(king_square, chess_move.origin).get_bit(chess_move.target)
     }
 
     pub fn make_move(&mut self, chess_move: ChessMove) -> Result<(), MakeMoveError> {
-        if mg::gen_moves(self).contains(&chess_move) {
-            // SAFETY: Move was generated for this board by the legal move generator
+        if self.is_legal(chess_move) {
+            // SAFETY: Move was checked to be legal for this board
             unsafe {
                 self.make_move_unchecked(chess_move);
             }
@@ -301,6 +789,139 @@ impl Board {
         }
     }
 
+    /// Checks if neither side has enough material left to ever checkmate the other. This is the
+    /// case when only kings remain, when one side has a single minor piece besides its king, or
+    /// when every remaining bishop stands on squares of the same color.
+    pub fn has_insufficient_material(&self) -> bool {
+        let knights = self.us.knights | self.them.knights;
+        let bishops = self.us.bishops | self.them.bishops;
+
+        let has_major_pieces_or_pawns = !(self.us.pawns
+            | self.them.pawns
+            | self.us.rooks
+            | self.them.rooks
+            | self.us.queens
+            | self.them.queens)
+            .is_empty();
+
+        if has_major_pieces_or_pawns {
+            return false;
+        }
+
+        match (knights.count_ones(), bishops.count_ones()) {
+            (0, _) => {
+                bishops.is_subset_of(BitBoard::LIGHT_SQUARES)
+                    || bishops.is_subset_of(BitBoard::DARK_SQUARES)
+            }
+            (1, 0) => true,
+            _ => false,
+        }
+    }
+
+    /// Recomputes every cached field of the board from the piece bitboards, reporting each one
+    /// which doesn't match. This is meant to catch bugs in move making, and is run after every
+    /// move when the `validate-invariants` feature is enabled.
+    pub fn validate_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut broken = vec![];
+
+        let (white, black) = match self.playing_color {
+            Color::White => (self.us, self.them),
+            Color::Black => (self.them, self.us),
+        };
+
+        let mut fresh = *self;
+        let mut overlapping = false;
+
+        for player in [&mut fresh.us, &mut fresh.them] {
+            let mut piece_count = 0;
+            player.occupation = BitBoard::EMPTY;
+
+            for kind in PieceKind::ALL {
+                piece_count += player.piece_bitboard(kind).count_ones();
+                player.occupation |= player.piece_bitboard(kind);
+            }
+
+            overlapping |= piece_count != player.occupation.count_ones();
+        }
+
+        let kings_valid = self.us.king.is_a_single_one() && self.them.king.is_a_single_one();
+
+        if !kings_valid {
+            broken.push(Invariant::KingCount);
+        }
+
+        if overlapping || !(fresh.us.occupation & fresh.them.occupation).is_empty() {
+            broken.push(Invariant::OverlappingPieces);
+        }
+
+        if fresh.us.occupation != self.us.occupation
+            || fresh.them.occupation != self.them.occupation
+        {
+            broken.push(Invariant::Occupation);
+        }
+
+        let piece_board = players_piece_board(&white, &black);
+
+        if piece_board.uncolored() != self.piece_kind_board {
+            broken.push(Invariant::PieceKindBoard);
+        }
+
+        // Move restrictions can only be found with a single king to restrict the moves of
+        if kings_valid {
+            fresh.checkers = BitBoard::EMPTY;
+            fresh.pinned = BitBoard::EMPTY;
+            fresh.update_move_restrictions();
+
+            if fresh.checkers != self.checkers {
+                broken.push(Invariant::Checkers);
+            }
+
+            if fresh.pinned != self.pinned {
+                broken.push(Invariant::Pinned);
+            }
+        }
+
+        let castling_rights_valid = [(&white, Color::White), (&black, Color::Black)]
+            .into_iter()
+            .all(|(player, color)| {
+                Square::ALL.into_iter().all(|square| {
+                    !player.castling_rights[square]
+                        || (square.rank() == back_rank(color)
+                            && (player.king | player.rooks).get_bit(square))
+                })
+            });
+
+        if !castling_rights_valid {
+            broken.push(Invariant::CastlingRights);
+        }
+
+        let hash = position_hash(
+            &piece_board,
+            &white,
+            &black,
+            self.playing_color,
+            self.en_passant_capture_square,
+        );
+
+        if hash != self.hash {
+            broken.push(Invariant::Hash);
+        }
+
+        if broken.is_empty() {
+            Ok(())
+        } else {
+            Err(InvariantViolation { broken })
+        }
+    }
+
+    // Panics if a move broke the invariants of the board, when checking them is enabled.
+    #[cfg(feature = "validate-invariants")]
+    fn assert_invariants(&self, chess_move: ChessMove) {
+        if let Err(violation) = self.validate_invariants() {
+            panic!("{violation} after {chess_move} on {self}");
+        }
+    }
+
     fn piece_board(&self) -> PieceBoard {
         PieceBoard::new(Square::ALL.map(|square| self.piece(square)))
     }
@@ -317,7 +938,9 @@ impl Board {
                 .into_iter()
                 .map(|chess_move| {
                     let mut new_board = *self;
-                    new_board.make_move(chess_move).unwrap();
+
+                    // SAFETY: Move was generated for this board by the legal move generator
+                    unsafe { new_board.make_move_unchecked(chess_move) };
 
                     new_board.perft(depth - 1)
                 })
@@ -325,16 +948,58 @@ impl Board {
         }
     }
 
+    /// Like [`Board::perft`], but walks a single board in place using
+    /// [`Board::make_move_unchecked`] and [`Board::unmake_move`] instead of copying it for each
+    /// move.
+    pub fn perft_in_place(&mut self, depth: u32) -> u64 {
+        let moves = mg::gen_moves(self);
+
+        match depth {
+            0 => 1,
+            1 => moves.len() as u64,
+            _ => moves
+                .into_iter()
+                .map(|chess_move| {
+                    // SAFETY: Move was generated for this board by the legal move generator
+                    let undo = unsafe { self.make_move_unchecked(chess_move) };
+                    let nodes = self.perft_in_place(depth - 1);
+
+                    // SAFETY: This is the last move made, and the undo record is the one it
+                    // returned
+                    unsafe { self.unmake_move(chess_move, undo) };
+
+                    nodes
+                })
+                .sum(),
+        }
+    }
+
     pub fn gen_child_boards(&self) -> impl Iterator<Item = (ChessMove, Board)> + '_ {
         mg::gen_moves(self).into_iter().map(|chess_move| {
             let mut new_board = *self;
-            new_board.make_move(chess_move).unwrap();
+
+            // SAFETY: Move was generated for this board by the legal move generator
+            unsafe { new_board.make_move_unchecked(chess_move) };
 
             (chess_move, new_board)
         })
     }
 }
 
+// The en passant file is only hashed when a pawn could actually capture en passant, so that
+// positions differing only by an unusable en passant square are considered the same.
+fn en_passant_hash(
+    en_passant_capture_square: Option<Square>,
+    capturing_color: Color,
+    capturing_pawns: BitBoard,
+) -> u64 {
+    en_passant_capture_square
+        .filter(|&square| {
+            !(index::pawn_attacks(square, !capturing_color) & capturing_pawns).is_empty()
+        })
+        .map_or(0, |square| zobrist::en_passant_file(square.file()))
+}
+
 #[derive(Debug, Clone, thiserror::Error)]
 #[non_exhaustive]
 pub enum ParseBoardError {
@@ -413,31 +1078,39 @@ impl FromStr for Board {
             }
         }
 
-        // When creating a board from FEN, the rook squares encode castling, and since
-        // CastlingRights uses the conjunction of the king and rook squares, they are assigned
-        // `true` here.
-        white.castling_rights[Square::E1] = true;
-        black.castling_rights[Square::E8] = true;
-
-        if castling_rights_string != "-" {
-            white.castling_rights[Square::H1] = castling_rights_string.contains('K');
-            white.castling_rights[Square::A1] = castling_rights_string.contains('Q');
-            black.castling_rights[Square::H8] = castling_rights_string.contains('k');
-            black.castling_rights[Square::A8] = castling_rights_string.contains('q');
-
-            // Assuming each match was singular and the part has no invalid characters,
-            // this would be the length of the part.
-            let assumed_length = (white.castling_rights[Square::H1] as usize)
-                + (white.castling_rights[Square::A1] as usize)
-                + (black.castling_rights[Square::H8] as usize)
-                + (black.castling_rights[Square::A8] as usize);
-
-            // If the assumed length is not the actual one, the part has some invalid
-            // characters, or repetitions
-            if castling_rights_string.is_empty() || assumed_length != castling_rights_string.len() {
-                return Err(ParseBoardError::InvalidCastlingRights);
-            }
-        }
+        let castling_mode = if castling_rights_string == "-" {
+            CastlingMode::Standard
+        } else if castling_rights_string.is_empty() {
+            return Err(ParseBoardError::InvalidCastlingRights);
+        } else {
+            parse_castling_rights(castling_rights_string, &mut white, &mut black)?
+        };
+
+        Self::from_players(
+            white,
+            black,
+            current_color,
+            en_passant_capture_square,
+            ply_clock,
+            full_moves,
+            castling_mode,
+        )
+    }
+}
+
+impl Board {
+    /// Builds a board from the pieces and castling rights of each player, checking that it is
+    /// valid, and computing its hash and move restrictions.
+    pub fn from_players(
+        white: Player,
+        black: Player,
+        current_color: Color,
+        en_passant_capture_square: Option<Square>,
+        ply_clock: u8,
+        full_moves: u16,
+        castling_mode: CastlingMode,
+    ) -> Result<Self, ParseBoardError> {
+        let piece_board = players_piece_board(&white, &black);
 
         let (current_player, opposing_player) = match current_color {
             Color::White => (white, black),
@@ -450,16 +1123,18 @@ impl FromStr for Board {
             playing_color: current_color,
             piece_kind_board: piece_board.uncolored(),
             en_passant_capture_square,
-            hash: zobrist::piece_table(&piece_board)
-                ^ zobrist::side(current_color)
-                ^ en_passant_capture_square
-                    .map_or(0, |square| zobrist::en_passant_file(square.file()))
-                ^ zobrist::castling_rights(&white.castling_rights)
-                ^ zobrist::castling_rights(&black.castling_rights),
+            hash: position_hash(
+                &piece_board,
+                &white,
+                &black,
+                current_color,
+                en_passant_capture_square,
+            ),
             checkers: BitBoard::EMPTY,
             pinned: BitBoard::EMPTY,
             min_ply_clock: ply_clock,
             full_moves,
+            castling_mode,
         };
 
         let is_impossible_en_passant_square = en_passant_capture_square.is_some_and(|square| {
@@ -470,14 +1145,13 @@ impl FromStr for Board {
                 })
             .is_empty();
 
-            let with_mismatching_pawn = piece_board
-                [unsafe { square.move_one_down_unchecked(current_color) }]
-                != Some(Piece {
-                    kind: PieceKind::Pawn,
-                    color: !current_color,
-                });
-
-            is_impossible_capture_square || with_mismatching_pawn
+            // The square below is only on the board once the rank has been checked.
+            is_impossible_capture_square
+                || piece_board[unsafe { square.move_one_down_unchecked(current_color) }]
+                    != Some(Piece {
+                        kind: PieceKind::Pawn,
+                        color: !current_color,
+                    })
         });
 
         if !board.us.king.is_a_single_one() || !board.them.king.is_a_single_one() {
@@ -496,23 +1170,187 @@ impl FromStr for Board {
     }
 }
 
-fn gen_castling_string(white: Player, black: Player) -> String {
-    let mut castling_string = String::new();
+// Finds the piece on each square from the bitboards of the players.
+fn players_piece_board(white: &Player, black: &Player) -> PieceBoard {
+    PieceBoard::new(Square::ALL.map(|square| {
+        [(white, Color::White), (black, Color::Black)]
+            .into_iter()
+            .find_map(|(player, color)| {
+                PieceKind::ALL
+                    .into_iter()
+                    .find(|&kind| player.piece_bitboard(kind).get_bit(square))
+                    .map(|kind| Piece { kind, color })
+            })
+    }))
+}
+
+// Returns whether any piece of the player of the passed color attacks the square, with sliding
+// attacks computed using the passed occupation. Unlike `Board::attackers_to`, only the pieces of a
+// single player are looked up, as this is checked for every king move during move generation.
+fn is_attacked_by(player: &Player, color: Color, square: Square, occupation: BitBoard) -> bool {
+    !(index::pawn_attacks(square, !color) & player.pawns).is_empty()
+        || !(index::knight_attacks(square) & player.knights).is_empty()
+        || !(index::king_attacks(square) & player.king).is_empty()
+        || !(index::rook_slides(square, occupation) & (player.rooks | player.queens)).is_empty()
+        || !(index::bishop_slides(square, occupation) & (player.bishops | player.queens)).is_empty()
+}
+
+// Computes the hash of a position from scratch, which the incremental updates made when making
+// moves must always agree with.
+fn position_hash(
+    piece_board: &PieceBoard,
+    white: &Player,
+    black: &Player,
+    current_color: Color,
+    en_passant_capture_square: Option<Square>,
+) -> u64 {
+    let current_player = match current_color {
+        Color::White => white,
+        Color::Black => black,
+    };
+
+    zobrist::piece_table(piece_board)
+        ^ zobrist::side(current_color)
+        ^ en_passant_hash(
+            en_passant_capture_square,
+            current_color,
+            current_player.pawns,
+        )
+        ^ zobrist::castling_rights(white, Color::White)
+        ^ zobrist::castling_rights(black, Color::Black)
+}
+
+// Parses the castling rights part of a FEN string, which may either use `KQkq` (referring to the
+// outermost rook on each side), or the files of the castling rooks, as in Shredder-FEN and X-FEN.
+// The returned castling mode is Chess960 when the rights can't be those of a standard game.
+fn parse_castling_rights(
+    castling_rights_string: &str,
+    white: &mut Player,
+    black: &mut Player,
+) -> Result<CastlingMode, ParseBoardError> {
+    let mut castling_mode = CastlingMode::Standard;
+
+    for character in castling_rights_string.chars() {
+        let (player, color) = if character.is_ascii_uppercase() {
+            (&mut *white, Color::White)
+        } else {
+            (&mut *black, Color::Black)
+        };
+
+        let rook_square = match character.to_ascii_lowercase() {
+            'k' => outermost_rook(player, color, CastlingSide::KingSide)?,
+            'q' => outermost_rook(player, color, CastlingSide::QueenSide)?,
+            file @ 'a'..='h' => Square::from_file_and_rank(file as u8 - b'a', back_rank(color)),
+            _ => return Err(ParseBoardError::InvalidCastlingRights),
+        };
 
-    if white.castling_rights.can_castle_king_side() {
-        castling_string.push('K');
+        if add_castling_right(player, color, rook_square)? {
+            castling_mode = CastlingMode::Chess960;
+        }
+    }
+
+    Ok(castling_mode)
+}
+
+// Returns the square of the player's king, if it is on its back rank as castling requires.
+fn castling_king_square(player: &Player, color: Color) -> Result<Square, ParseBoardError> {
+    Square::try_from(player.king)
+        .ok()
+        .filter(|square| square.rank() == back_rank(color))
+        .ok_or(ParseBoardError::InvalidCastlingRights)
+}
+
+// Returns the square of the outermost rook on the passed side of the player's king, on its back
+// rank, which is the castling rook referred to by `KQkq` castling rights.
+pub(crate) fn outermost_rook(
+    player: &Player,
+    color: Color,
+    side: CastlingSide,
+) -> Result<Square, ParseBoardError> {
+    let king_square = castling_king_square(player, color)?;
+    let rook_square = |file| Square::from_file_and_rank(file, king_square.rank());
+    let has_rook = |square: &Square| player.rooks.get_bit(*square);
+
+    match side {
+        CastlingSide::KingSide => (king_square.file() + 1..8)
+            .rev()
+            .map(rook_square)
+            .find(has_rook),
+        CastlingSide::QueenSide => (0..king_square.file()).map(rook_square).find(has_rook),
     }
+    .ok_or(ParseBoardError::InvalidCastlingRights)
+}
 
-    if white.castling_rights.can_castle_queen_side() {
-        castling_string.push('Q');
+// Gives the player the right to castle with the rook on the passed square, which must be on the
+// back rank along with the king. Returns whether the castle is only possible in Chess960.
+pub(crate) fn add_castling_right(
+    player: &mut Player,
+    color: Color,
+    rook_square: Square,
+) -> Result<bool, ParseBoardError> {
+    let king_square = castling_king_square(player, color)?;
+
+    if rook_square == king_square
+        || rook_square.rank() != king_square.rank()
+        || !player.rooks.get_bit(rook_square)
+    {
+        return Err(ParseBoardError::InvalidCastlingRights);
     }
 
-    if black.castling_rights.can_castle_king_side() {
-        castling_string.push('k');
+    let side = if rook_square.file() > king_square.file() {
+        CastlingSide::KingSide
+    } else {
+        CastlingSide::QueenSide
+    };
+
+    // Each side can only be castled on with a single rook
+    if player.castling_rook(side).is_some() {
+        return Err(ParseBoardError::InvalidCastlingRights);
     }
 
-    if black.castling_rights.can_castle_queen_side() {
-        castling_string.push('q');
+    player.castling_rights[king_square] = true;
+    player.castling_rights[rook_square] = true;
+
+    Ok(king_square.file() != Square::E_FILE
+        || (rook_square.file() != Square::A_FILE && rook_square.file() != Square::H_FILE))
+}
+
+// Generates the castling rights part of a FEN string. In X-FEN, the rights are written as `KQkq`
+// unless the castling rook isn't the outermost rook on its side, in which case its file is used
+// instead. In Shredder-FEN, the file is always used.
+fn gen_castling_string(white: Player, black: Player, shredder: bool) -> String {
+    let mut castling_string = String::new();
+
+    for (player, color) in [(white, Color::White), (black, Color::Black)] {
+        for side in CastlingSide::ALL {
+            let Some(rook_square) = player.castling_rook(side) else {
+                continue;
+            };
+
+            let outer_files = match side {
+                CastlingSide::KingSide => rook_square.file() + 1..8,
+                CastlingSide::QueenSide => 0..rook_square.file(),
+            };
+            let is_outermost_rook = outer_files.into_iter().all(|file| {
+                !player
+                    .rooks
+                    .get_bit(Square::from_file_and_rank(file, rook_square.rank()))
+            });
+
+            let character = if shredder || !is_outermost_rook {
+                (b'A' + rook_square.file()) as char
+            } else {
+                match side {
+                    CastlingSide::KingSide => 'K',
+                    CastlingSide::QueenSide => 'Q',
+                }
+            };
+
+            castling_string.push(match color {
+                Color::White => character,
+                Color::Black => character.to_ascii_lowercase(),
+            });
+        }
     }
 
     if castling_string.is_empty() {
@@ -522,8 +1360,8 @@ fn gen_castling_string(white: Player, black: Player) -> String {
     castling_string
 }
 
-impl Display for Board {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Board {
+    fn gen_fen(&self, shredder: bool) -> String {
         let (white, black) = match self.playing_color {
             Color::White => (self.us, self.them),
             Color::Black => (self.them, self.us),
@@ -535,15 +1373,73 @@ impl Display for Board {
             .map(ToString::to_string)
             .unwrap_or(String::from("-"));
 
-        write!(
-            f,
+        format!(
             "{} {} {} {} {} {}",
             self.piece_board(),
             self.playing_color,
-            gen_castling_string(white, black),
+            gen_castling_string(white, black, shredder),
             en_passant_capture_square_string,
             self.min_ply_clock,
             self.full_moves
         )
     }
+
+    /// Formats the board as Shredder-FEN, where castling rights are always written using the files
+    /// of the castling rooks, such as `HAha` for the starting position. [`Board`]'s [`Display`]
+    /// implementation instead formats the board as X-FEN, which is identical to standard FEN
+    /// for standard chess positions.
+    pub fn to_shredder_fen(&self) -> String {
+        self.gen_fen(true)
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.gen_fen(false))
+    }
+}
+
+/// Boards are serialized as their FEN string in human-readable formats, and as their
+/// [`PackedBoard`](crate::packed::PackedBoard) encoding otherwise. Chess960 boards are written as
+/// Shredder-FEN, as castling rights written with the files of the rooks are read back as Chess960
+/// ones. The castling mode of boards without castling rights is lost.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Board {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            serde::Serialize::serialize(&self.to_packed().0, serializer)
+        } else if self.castling_mode == CastlingMode::Chess960 {
+            serializer.serialize_str(&self.to_shredder_fen())
+        } else {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        if deserializer.is_human_readable() {
+            let fen = String::deserialize(deserializer)?;
+            let mut board = Board::from_str(&fen).map_err(D::Error::custom)?;
+
+            // Standard boards are written with `KQkq` castling rights, so the files of the rooks
+            // are only used by Chess960 ones.
+            let castling_rights = fen.split_whitespace().nth(2).unwrap_or_default();
+
+            if castling_rights
+                .chars()
+                .any(|character| matches!(character.to_ascii_lowercase(), 'a'..='h'))
+            {
+                board.castling_mode = CastlingMode::Chess960;
+            }
+
+            Ok(board)
+        } else {
+            Board::from_packed(&crate::packed::PackedBoard(<_>::deserialize(deserializer)?))
+                .map_err(D::Error::custom)
+        }
+    }
 }
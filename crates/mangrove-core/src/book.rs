@@ -0,0 +1,189 @@
+use std::io::{self, Read};
+
+use mangrove_bootstrap::{Color, Square};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+
+use crate::{
+    board::Board,
+    index::{self, polyglot},
+    repr::{CastlingSide, ChessMove, PieceKind},
+};
+
+/// The number of bytes taken by each entry of a Polyglot book.
+const ENTRY_SIZE: usize = 16;
+
+impl Board {
+    /// Returns the key of the board in the Polyglot opening book format. Unlike [`Board::hash`],
+    /// the en passant file is only part of the key when a pawn of the side to move stands next to
+    /// the double-pushed pawn, regardless of whether capturing it is legal.
+    pub fn polyglot_key(&self) -> u64 {
+        let mut key = Square::ALL
+            .into_iter()
+            .filter_map(|square| {
+                self.piece(square)
+                    .map(|piece| polyglot::piece(piece, square))
+            })
+            .fold(0, |key, piece_key| key ^ piece_key);
+
+        for color in [Color::White, Color::Black] {
+            for side in CastlingSide::ALL {
+                if self.player(color).castling_rook(side).is_some() {
+                    key ^= polyglot::castling_right(color, side);
+                }
+            }
+        }
+
+        if let Some(square) = self.en_passant_capture_square {
+            if !(index::pawn_attacks(square, !self.playing_color) & self.us.pawns).is_empty() {
+                key ^= polyglot::en_passant_file(square.file());
+            }
+        }
+
+        if self.playing_color == Color::White {
+            key ^= polyglot::white_to_move();
+        }
+
+        key
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ReadPolyglotBookError {
+    #[error("failed to read book")]
+    Io(#[source] io::Error),
+    #[error("book length must be a multiple of 16 bytes")]
+    InvalidLength,
+    #[error("book entries must be sorted by key")]
+    UnsortedEntries,
+}
+
+/// A single entry of a Polyglot book, suggesting a move for the positions with its key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolyglotEntry {
+    pub key: u64,
+    /// The move, in the Polyglot encoding. See [`PolyglotEntry::chess_move`].
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl PolyglotEntry {
+    /// Decodes the move of the entry for the passed board, returning `None` if it isn't legal.
+    /// Polyglot encodes castles as the king moving onto its rook, which are converted to the
+    /// castling mode of the board.
+    pub fn chess_move(&self, board: &Board) -> Option<ChessMove> {
+        let square =
+            |bits: u16| Square::from_file_and_rank((bits & 0b111) as u8, (bits >> 3 & 0b111) as u8);
+
+        let origin = square(self.raw_move >> 6);
+        let target = square(self.raw_move);
+        let promotion = match self.raw_move >> 12 & 0b111 {
+            0 => None,
+            1 => Some(PieceKind::Knight),
+            2 => Some(PieceKind::Bishop),
+            3 => Some(PieceKind::Rook),
+            4 => Some(PieceKind::Queen),
+            _ => return None,
+        };
+
+        let chess_move = if board.us.king.get_bit(origin) && board.us.rooks.get_bit(target) {
+            let side = if target.file() > origin.file() {
+                CastlingSide::KingSide
+            } else {
+                CastlingSide::QueenSide
+            };
+
+            board.castling_move(side, target)
+        } else {
+            ChessMove {
+                origin,
+                target,
+                promotion,
+            }
+        };
+
+        board.is_legal(chess_move).then_some(chess_move)
+    }
+}
+
+/// An opening book in the Polyglot format, made of entries sorted by key.
+#[derive(Debug, Clone, Default)]
+pub struct PolyglotBook {
+    entries: Vec<PolyglotEntry>,
+}
+
+impl PolyglotBook {
+    /// Reads a book from the contents of a Polyglot `.bin` file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReadPolyglotBookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(ReadPolyglotBookError::InvalidLength);
+        }
+
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| PolyglotEntry {
+                key: u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(entry[12..16].try_into().unwrap()),
+            })
+            .collect::<Vec<_>>();
+
+        if entries.windows(2).any(|pair| pair[0].key > pair[1].key) {
+            return Err(ReadPolyglotBookError::UnsortedEntries);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Reads a book from a reader of a Polyglot `.bin` file, until its end.
+    pub fn read(mut reader: impl Read) -> Result<Self, ReadPolyglotBookError> {
+        let mut bytes = vec![];
+
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(ReadPolyglotBookError::Io)?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the entries of the book for the passed board, found by binary search.
+    pub fn entries(&self, board: &Board) -> &[PolyglotEntry] {
+        let key = board.polyglot_key();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+
+        &self.entries[start..end]
+    }
+
+    /// Returns the legal moves of the book for the passed board, along with their weights.
+    pub fn moves(&self, board: &Board) -> Vec<(ChessMove, u16)> {
+        self.entries(board)
+            .iter()
+            .filter_map(|entry| Some((entry.chess_move(board)?, entry.weight)))
+            .collect()
+    }
+
+    /// Returns the book move with the highest weight for the passed board, preferring the first
+    /// entry on ties.
+    pub fn best_move(&self, board: &Board) -> Option<ChessMove> {
+        self.moves(board)
+            .into_iter()
+            .rev()
+            .max_by_key(|&(_, weight)| weight)
+            .map(|(chess_move, _)| chess_move)
+    }
+
+    /// Returns a random book move for the passed board, chosen with a probability proportional
+    /// to its weight. Returns `None` if there are no moves with a positive weight.
+    pub fn weighted_random_move(&self, board: &Board, rng: &mut impl Rng) -> Option<ChessMove> {
+        let moves = self.moves(board);
+        let distribution = WeightedIndex::new(moves.iter().map(|&(_, weight)| weight)).ok()?;
+
+        Some(moves[distribution.sample(rng)].0)
+    }
+}
@@ -0,0 +1,193 @@
+use mangrove_bootstrap::{Color, Square};
+
+use crate::{
+    board::{add_castling_right, outermost_rook, Board, ParseBoardError},
+    repr::{CastlingMode, CastlingSide, Piece, PieceKind, Player},
+};
+
+// A castling right given to a builder, which is resolved to a rook when building the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CastlingRook {
+    // The outermost rook on the side, as with `KQkq` in FEN.
+    Outermost(CastlingSide),
+    Square(Square),
+}
+
+/// Sets up a [`Board`] piece by piece, validating it only once it's built, in the same way as
+/// parsing a FEN string. It starts empty, with white to move on the first move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardBuilder {
+    players: [Player; 2],
+    playing_color: Color,
+    castling_rooks: Vec<(Color, CastlingRook)>,
+    en_passant_capture_square: Option<Square>,
+    ply_clock: u8,
+    full_moves: u16,
+    castling_mode: CastlingMode,
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self {
+            players: [Player::blank(), Player::blank()],
+            playing_color: Color::White,
+            castling_rooks: vec![],
+            en_passant_capture_square: None,
+            ply_clock: 0,
+            full_moves: 1,
+            castling_mode: CastlingMode::Standard,
+        }
+    }
+}
+
+impl From<Board> for BoardBuilder {
+    fn from(board: Board) -> Self {
+        let mut builder = Self {
+            playing_color: board.playing_color,
+            en_passant_capture_square: board.en_passant_capture_square,
+            ply_clock: board.min_ply_clock,
+            full_moves: board.full_moves,
+            castling_mode: board.castling_mode,
+            ..Self::default()
+        };
+
+        for color in [Color::White, Color::Black] {
+            let player = board.player(color);
+
+            for side in CastlingSide::ALL {
+                if let Some(rook_square) = player.castling_rook(side) {
+                    builder = builder.castling_rook(color, rook_square);
+                }
+            }
+
+            for kind in PieceKind::ALL {
+                for square in player.piece_bitboard(kind).bits() {
+                    builder = builder.place(square, Piece { kind, color });
+                }
+            }
+        }
+
+        builder
+    }
+}
+
+impl BoardBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the piece on the square, if there is one.
+    pub fn piece(&self, square: Square) -> Option<Piece> {
+        [Color::White, Color::Black].into_iter().find_map(|color| {
+            PieceKind::ALL
+                .into_iter()
+                .find(|&kind| {
+                    self.players[color as usize]
+                        .piece_bitboard(kind)
+                        .get_bit(square)
+                })
+                .map(|kind| Piece { kind, color })
+        })
+    }
+
+    /// Places the piece on the square, replacing any piece already there.
+    pub fn place(self, square: Square, piece: Piece) -> Self {
+        let mut builder = self.remove(square);
+        builder.players[piece.color as usize].toggle_piece(square, piece.kind);
+        builder
+    }
+
+    /// Removes the piece on the square, if there is one.
+    pub fn remove(mut self, square: Square) -> Self {
+        if let Some(Piece { kind, color }) = self.piece(square) {
+            self.players[color as usize].toggle_piece(square, kind);
+        }
+
+        self
+    }
+
+    pub fn playing_color(mut self, color: Color) -> Self {
+        self.playing_color = color;
+        self
+    }
+
+    /// Gives the right to castle with the outermost rook on the side of the king, as `KQkq` do in
+    /// FEN. Giving a player two rights on the same side makes the board invalid.
+    pub fn castling_right(mut self, color: Color, side: CastlingSide) -> Self {
+        self.castling_rooks
+            .push((color, CastlingRook::Outermost(side)));
+        self
+    }
+
+    /// Gives the right to castle with the rook on the square, as Shredder-FEN does. Rights which
+    /// can't be those of a standard game make the board use Chess960 castling.
+    pub fn castling_rook(mut self, color: Color, rook_square: Square) -> Self {
+        self.castling_rooks
+            .push((color, CastlingRook::Square(rook_square)));
+        self
+    }
+
+    /// Takes away the castling rights of both players.
+    pub fn clear_castling_rights(mut self) -> Self {
+        self.castling_rooks.clear();
+        self
+    }
+
+    pub fn en_passant_capture_square(mut self, square: Option<Square>) -> Self {
+        self.en_passant_capture_square = square;
+        self
+    }
+
+    /// Sets the number of plies since the last capture or pawn move, for the fifty-move rule.
+    pub fn ply_clock(mut self, ply_clock: u8) -> Self {
+        self.ply_clock = ply_clock;
+        self
+    }
+
+    pub fn full_moves(mut self, full_moves: u16) -> Self {
+        self.full_moves = full_moves;
+        self
+    }
+
+    /// Sets how castles are encoded in moves. Chess960 castling is used regardless when the
+    /// castling rights can't be those of a standard game.
+    pub fn castling_mode(mut self, castling_mode: CastlingMode) -> Self {
+        self.castling_mode = castling_mode;
+        self
+    }
+
+    /// Builds the board, checking it as parsing a FEN string would.
+    pub fn build(&self) -> Result<Board, ParseBoardError> {
+        if self.ply_clock as u16 > self.full_moves.saturating_mul(2) {
+            return Err(ParseBoardError::IllegalHalfMoveClock);
+        }
+
+        let mut players = self.players;
+        let mut castling_mode = self.castling_mode;
+
+        for &(color, castling_rook) in &self.castling_rooks {
+            let player = &mut players[color as usize];
+
+            let rook_square = match castling_rook {
+                CastlingRook::Outermost(side) => outermost_rook(player, color, side)?,
+                CastlingRook::Square(square) => square,
+            };
+
+            if add_castling_right(player, color, rook_square)? {
+                castling_mode = CastlingMode::Chess960;
+            }
+        }
+
+        let [white, black] = players;
+
+        Board::from_players(
+            white,
+            black,
+            self.playing_color,
+            self.en_passant_capture_square,
+            self.ply_clock,
+            self.full_moves,
+            castling_mode,
+        )
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    fmt::{self, Display},
+    num::ParseIntError,
+    str::FromStr,
+};
+
+use crate::{
+    board::{Board, ParseBoardError},
+    repr::ChessMove,
+};
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum ParseEpdError {
+    #[error("epd should start with 4 space-separated position parts")]
+    InvalidPartAmount,
+    #[error("position is invalid")]
+    InvalidBoard(#[source] ParseBoardError),
+    #[error("string operand must be closed by a quote")]
+    UnterminatedString,
+    #[error("operation `{0}` has an invalid amount of operands")]
+    InvalidOperandAmount(String),
+    #[error("operand of operation `{0}` should be an integer")]
+    InvalidInteger(String, #[source] ParseIntError),
+    #[error("move `{0}` is neither legal san nor a legal move in long algebraic notation")]
+    InvalidMove(String),
+}
+
+/// A single operation of an EPD record, made of an opcode and its operands.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EpdOperation {
+    /// `bm`: The best moves of the position.
+    BestMoves(Vec<ChessMove>),
+    /// `am`: The moves to avoid in the position.
+    AvoidMoves(Vec<ChessMove>),
+    /// `id`: The identifier of the record.
+    Id(String),
+    /// `c0` to `c9`: A comment, along with its number.
+    Comment(u8, String),
+    /// `ce`: The evaluation of the position in centipawns, from the view of the side to move.
+    CentipawnEvaluation(i32),
+    /// `pv`: The principal variation, starting from the position.
+    PrincipalVariation(Vec<ChessMove>),
+    /// `hmvc`: The half-move clock.
+    HalfMoveClock(u8),
+    /// `fmvn`: The full-move number.
+    FullMoveNumber(u16),
+    /// Any other operation, with its opcode and unparsed operands.
+    Other(String, Vec<String>),
+}
+
+/// A position in Extended Position Description (EPD) format, along with its operations.
+///
+/// When parsed, moves may be written both in SAN and in long algebraic notation, and are
+/// resolved against the position. They are always written back in SAN. The half-move clock and
+/// full-move number of the board are taken from the `hmvc` and `fmvn` operations, and default
+/// to `0` and `1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpdRecord {
+    pub board: Board,
+    pub operations: Vec<EpdOperation>,
+}
+
+impl EpdRecord {
+    /// Returns the moves of the first `bm` operation, if any.
+    pub fn best_moves(&self) -> Option<&[ChessMove]> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                EpdOperation::BestMoves(moves) => Some(&moves[..]),
+                _ => None,
+            })
+    }
+
+    /// Returns the moves of the first `am` operation, if any.
+    pub fn avoid_moves(&self) -> Option<&[ChessMove]> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                EpdOperation::AvoidMoves(moves) => Some(&moves[..]),
+                _ => None,
+            })
+    }
+
+    /// Returns the operand of the first `id` operation, if any.
+    pub fn id(&self) -> Option<&str> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                EpdOperation::Id(id) => Some(&id[..]),
+                _ => None,
+            })
+    }
+}
+
+fn parse_move(board: &Board, operand: &str) -> Result<ChessMove, ParseEpdError> {
+    board
+        .parse_san(operand)
+        .ok()
+        .or_else(|| {
+            ChessMove::from_str(operand)
+                .ok()
+                .filter(|&chess_move| board.is_legal(chess_move))
+        })
+        .ok_or_else(|| ParseEpdError::InvalidMove(operand.to_string()))
+}
+
+fn parse_integer<T: FromStr<Err = ParseIntError>>(
+    opcode: &str,
+    operands: &[String],
+) -> Result<T, ParseEpdError> {
+    match operands {
+        [operand] => operand
+            .parse()
+            .map_err(|error| ParseEpdError::InvalidInteger(opcode.to_string(), error)),
+        _ => Err(ParseEpdError::InvalidOperandAmount(opcode.to_string())),
+    }
+}
+
+// Resolves the moves of an operation, each against the board reached by playing the previous
+// ones if the moves form a sequence.
+fn parse_moves(
+    board: &Board,
+    opcode: &str,
+    operands: &[String],
+    is_sequence: bool,
+) -> Result<Vec<ChessMove>, ParseEpdError> {
+    if operands.is_empty() {
+        return Err(ParseEpdError::InvalidOperandAmount(opcode.to_string()));
+    }
+
+    let mut board = *board;
+
+    operands
+        .iter()
+        .map(|operand| {
+            let chess_move = parse_move(&board, operand)?;
+
+            if is_sequence {
+                board.make_move(chess_move).unwrap();
+            }
+
+            Ok(chess_move)
+        })
+        .collect()
+}
+
+impl EpdOperation {
+    fn parse(board: &Board, opcode: String, operands: Vec<String>) -> Result<Self, ParseEpdError> {
+        let single_operand = |operands: Vec<String>| match <[String; 1]>::try_from(operands) {
+            Ok([operand]) => Ok(operand),
+            Err(_) => Err(ParseEpdError::InvalidOperandAmount(opcode.clone())),
+        };
+
+        Ok(match &opcode[..] {
+            "bm" => Self::BestMoves(parse_moves(board, &opcode, &operands, false)?),
+            "am" => Self::AvoidMoves(parse_moves(board, &opcode, &operands, false)?),
+            "pv" => Self::PrincipalVariation(parse_moves(board, &opcode, &operands, true)?),
+            "id" => Self::Id(single_operand(operands)?),
+            "ce" => Self::CentipawnEvaluation(parse_integer(&opcode, &operands)?),
+            "hmvc" => Self::HalfMoveClock(parse_integer(&opcode, &operands)?),
+            "fmvn" => Self::FullMoveNumber(parse_integer(&opcode, &operands)?),
+            _ => match opcode.as_bytes() {
+                &[b'c', digit @ b'0'..=b'9'] => {
+                    Self::Comment(digit - b'0', single_operand(operands)?)
+                }
+                _ => Self::Other(opcode, operands),
+            },
+        })
+    }
+}
+
+// Splits the operations of a record into their opcodes and operands.
+fn split_operations(s: &str) -> Result<Vec<(String, Vec<String>)>, ParseEpdError> {
+    let mut operations = vec![];
+    let mut chars = s.chars().peekable();
+
+    loop {
+        while chars.next_if(|char| char.is_whitespace()).is_some() {}
+
+        let Some(&char) = chars.peek() else {
+            break;
+        };
+
+        // Stray semicolons are ignored
+        if char == ';' {
+            chars.next();
+            continue;
+        }
+
+        let mut opcode = String::new();
+
+        while let Some(char) = chars.next_if(|&char| !char.is_whitespace() && char != ';') {
+            opcode.push(char);
+        }
+
+        let mut operands = vec![];
+
+        loop {
+            while chars.next_if(|char| char.is_whitespace()).is_some() {}
+
+            match chars.next() {
+                // The last operation's semicolon is commonly omitted, so it is not required
+                None | Some(';') => break,
+                Some('"') => {
+                    let mut operand = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') if matches!(chars.peek(), Some('"' | '\\')) => {
+                                operand.push(chars.next().unwrap())
+                            }
+                            Some(char) => operand.push(char),
+                            None => return Err(ParseEpdError::UnterminatedString),
+                        }
+                    }
+
+                    operands.push(operand);
+                }
+                Some(char) => {
+                    let mut operand = char.to_string();
+
+                    while let Some(char) =
+                        chars.next_if(|&char| !char.is_whitespace() && char != ';')
+                    {
+                        operand.push(char);
+                    }
+
+                    operands.push(operand);
+                }
+            }
+        }
+
+        operations.push((opcode, operands));
+    }
+
+    Ok(operations)
+}
+
+impl FromStr for EpdRecord {
+    type Err = ParseEpdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s.trim_start();
+        let mut position_parts = vec![];
+
+        for _ in 0..4 {
+            let (part, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+            if part.is_empty() {
+                return Err(ParseEpdError::InvalidPartAmount);
+            }
+
+            position_parts.push(part);
+            rest = remainder.trim_start();
+        }
+
+        let mut board = Board::from_str(&format!("{} 0 1", position_parts.join(" ")))
+            .map_err(ParseEpdError::InvalidBoard)?;
+
+        let operations = split_operations(rest)?
+            .into_iter()
+            .map(|(opcode, operands)| EpdOperation::parse(&board, opcode, operands))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for operation in &operations {
+            match *operation {
+                EpdOperation::HalfMoveClock(clock) => board.min_ply_clock = clock,
+                EpdOperation::FullMoveNumber(number) => board.full_moves = number,
+                _ => {}
+            }
+        }
+
+        Ok(Self { board, operations })
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, string: &str) -> fmt::Result {
+    write!(
+        f,
+        "\"{}\"",
+        string.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+impl Display for EpdRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fen = self.board.to_string();
+        let position = fen.split(' ').take(4).collect::<Vec<_>>().join(" ");
+
+        f.write_str(&position)?;
+
+        for operation in &self.operations {
+            match operation {
+                EpdOperation::BestMoves(moves) | EpdOperation::AvoidMoves(moves) => {
+                    f.write_str(match operation {
+                        EpdOperation::BestMoves(_) => " bm",
+                        _ => " am",
+                    })?;
+
+                    for &chess_move in moves {
+                        write!(f, " {}", self.board.move_to_san(chess_move))?;
+                    }
+                }
+                EpdOperation::PrincipalVariation(moves) => {
+                    let mut board = self.board;
+
+                    f.write_str(" pv")?;
+
+                    for &chess_move in moves {
+                        write!(f, " {}", board.move_to_san(chess_move))?;
+                        board.make_move(chess_move).unwrap();
+                    }
+                }
+                EpdOperation::Id(id) => {
+                    f.write_str(" id ")?;
+                    write_string(f, id)?;
+                }
+                EpdOperation::Comment(number, comment) => {
+                    write!(f, " c{number} ")?;
+                    write_string(f, comment)?;
+                }
+                EpdOperation::CentipawnEvaluation(evaluation) => write!(f, " ce {evaluation}")?,
+                EpdOperation::HalfMoveClock(clock) => write!(f, " hmvc {clock}")?,
+                EpdOperation::FullMoveNumber(number) => write!(f, " fmvn {number}")?,
+                EpdOperation::Other(opcode, operands) => {
+                    write!(f, " {opcode}")?;
+
+                    for operand in operands {
+                        f.write_str(" ")?;
+
+                        if operand.is_empty()
+                            || operand.contains(|char: char| {
+                                char.is_whitespace() || matches!(char, ';' | '"' | '\\')
+                            })
+                        {
+                            write_string(f, operand)?;
+                        } else {
+                            f.write_str(operand)?;
+                        }
+                    }
+                }
+            }
+
+            f.write_str(";")?;
+        }
+
+        Ok(())
+    }
+}
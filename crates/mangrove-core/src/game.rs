@@ -8,47 +8,129 @@ use crate::{
     repr::ChessMove,
 };
 
+/// The reason a game ended in a draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DrawReason {
+    /// The side to move has no legal moves, but isn't in check.
+    Stalemate,
+    /// The same position occurred three times, with the same side to move.
+    ThreefoldRepetition,
+    /// Fifty moves were played by each side without a capture or a pawn move.
+    FiftyMoveRule,
+    /// Neither side has enough material left to checkmate the other.
+    InsufficientMaterial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Outcome {
+    /// The passed color checkmated its opponent.
     Win(Color),
-    Draw,
+    Draw(DrawReason),
 }
 
+#[derive(Debug, Clone)]
 pub struct Game {
+    starting_board: Board,
     board: Board,
+    // The hashes of every position reached in the game, including the current one.
+    position_hashes: Vec<u64>,
+    moves: Vec<ChessMove>,
 }
 
 impl Game {
-    pub fn starting_position() -> Self {
+    /// Creates a game starting from the passed board.
+    pub fn new(board: Board) -> Self {
         Self {
-            board: Board::starting_position(),
+            starting_board: board,
+            board,
+            position_hashes: vec![board.hash],
+            moves: vec![],
         }
     }
 
+    pub fn starting_position() -> Self {
+        Self::new(Board::starting_position())
+    }
+
+    /// Counts the number of times the current position occurred in the game, including the
+    /// current occurrence.
+    pub fn repetitions(&self) -> usize {
+        // Positions can only repeat since the last capture or pawn move, and only when the same
+        // side is to move, which is every second position.
+        self.position_hashes
+            .iter()
+            .rev()
+            .skip(1)
+            .take(self.board.min_ply_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|&&hash| hash == self.board.hash)
+            .count()
+            + 1
+    }
+
     pub fn outcome(&self) -> Option<Outcome> {
         if mg::gen_moves(&self.board).is_empty() {
             Some(if self.board.in_check() {
                 Outcome::Win(!self.board.playing_color)
             } else {
-                Outcome::Draw
+                Outcome::Draw(DrawReason::Stalemate)
             })
+        } else if self.board.min_ply_clock >= 100 {
+            Some(Outcome::Draw(DrawReason::FiftyMoveRule))
+        } else if self.repetitions() >= 3 {
+            Some(Outcome::Draw(DrawReason::ThreefoldRepetition))
+        } else if self.board.has_insufficient_material() {
+            Some(Outcome::Draw(DrawReason::InsufficientMaterial))
         } else {
             None
         }
     }
 
     pub fn make_move(&mut self, chess_move: ChessMove) -> Result<(), MakeMoveError> {
-        self.board.make_move(chess_move)
+        self.board.make_move(chess_move)?;
+
+        self.position_hashes.push(self.board.hash);
+        self.moves.push(chess_move);
+
+        Ok(())
     }
 
     pub fn board(&self) -> &Board {
         &self.board
     }
+
+    /// Returns the board the game started from, before any of its moves were played.
+    pub fn starting_board(&self) -> &Board {
+        &self.starting_board
+    }
+
+    /// Returns the moves played in the game, in order.
+    pub fn moves(&self) -> &[ChessMove] {
+        &self.moves
+    }
+
+    /// Returns the hashes of every position reached in the game, in order, starting with the
+    /// initial position and ending with the current one.
+    pub fn position_hashes(&self) -> &[u64] {
+        &self.position_hashes
+    }
 }
 
 impl FromStr for Game {
     type Err = ParseBoardError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Board::from_str(s).map(|board| Self { board })
+        Board::from_str(s).map(Self::new)
     }
 }
@@ -1,14 +1,48 @@
 pub mod board;
+pub mod book;
+pub mod builder;
+pub mod epd;
 pub mod game;
 mod index;
+pub mod material;
 pub mod mg;
+pub mod packed;
+pub mod perft;
+pub mod pgn;
+pub mod random;
 pub mod repr;
+pub mod san;
+pub mod see;
+pub mod transform;
+
+// The serialization tests only use these when the `serde` feature is enabled
+#[cfg(all(test, not(feature = "serde")))]
+use {bincode as _, serde_json as _};
 
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
-    use crate::{board::Board, repr::ChessMove};
+    use crate::{
+        board::{
+            Board, Invariant, InvariantViolation, ParseBoardError, ZOBRIST_SEED, ZOBRIST_VERSION,
+        },
+        book::{PolyglotBook, ReadPolyglotBookError},
+        builder::BoardBuilder,
+        epd::{EpdOperation, EpdRecord},
+        game::{DrawReason, Game, Outcome},
+        index,
+        material::{MaterialSignature, ParseMaterialSignatureError},
+        mg,
+        packed::{PackedMove, UnpackBoardError},
+        pgn::{GameResult, MoveAnnotation, PgnErrorKind, PgnGame, PgnReader, PgnWriter},
+        random::{random_position, CheckConstraint, RandomPositionError, RandomPositionOptions},
+        repr::{CastlingMode, CastlingSide, ChessMove, MoveKind, Piece, PieceKind},
+        san::ParseSanError,
+        see::PieceValues,
+    };
+    use mangrove_bootstrap::{BitBoard, Color, Square};
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
     use test_case::test_case;
 
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "starting position")]
@@ -78,13 +112,170 @@ mod tests {
     #[test_case("r3k2r/p1pp1pb1/bn2Qnp1/2qPN3/1p2P3/2N5/PPPBBPPP/R3K2R b KQkq - 3 2", 1, 5; "misc 4 depth 1")]
     #[test_case("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 3, 62379; "misc 5 depth 3")]
     #[test_case("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 3, 89890; "misc 6 depth 3")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 4, 326672; "chess960 1 depth 4")]
+    #[test_case("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", 4, 667366; "chess960 2 depth 4")]
+    #[test_case("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", 4, 273318; "chess960 3 depth 4")]
+    #[test_case("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", 4, 382958; "chess960 4 depth 4")]
+    #[test_case("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", 4, 1171749; "chess960 5 depth 4")]
+    #[test_case("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", 4, 824055; "chess960 6 depth 4")]
+    #[test_case("q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9", 4, 732757; "chess960 7 depth 4")]
+    #[test_case("qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9", 4, 465806; "chess960 8 depth 4")]
+    #[test_case("qnnbbrkr/1p2ppp1/2pp3p/p7/1P5P/2NP4/P1P1PPP1/Q1NBBRKR w HFhf - 0 9", 4, 384260; "chess960 9 depth 4")]
+    #[test_case("qn1rbbkr/ppp2p1p/1n1pp1p1/8/3P4/P6P/1PP1PPPK/QNNRBB1R w hd - 2 9", 4, 679699; "chess960 10 depth 4")]
     fn perft_tests(position_fen: &str, depth: u32, expected_result: u64) {
+        let board = Board::from_str(position_fen).unwrap();
+        let mut in_place_board = board;
+
+        assert_eq!(board.perft(depth), expected_result);
+        assert_eq!(in_place_board.perft_in_place(depth), expected_result);
+        assert_eq!(in_place_board, board);
+    }
+
+    #[test]
+    fn setwise_attack_tests() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // Combines the attacks of each piece, as looked up square by square
+        let union = |pieces: BitBoard, attacks: &dyn Fn(Square) -> BitBoard| {
+            pieces
+                .bits()
+                .fold(BitBoard::EMPTY, |union, square| union | attacks(square))
+        };
+
+        for _ in 0..10_000 {
+            let occupation = BitBoard(rng.gen::<u64>() & rng.gen::<u64>());
+            let pieces = BitBoard(rng.gen::<u64>() & rng.gen::<u64>() & rng.gen::<u64>());
+            let empty = !(occupation | pieces);
+
+            assert_eq!(
+                BitBoard::rook_attacks_setwise(pieces, empty),
+                union(pieces, &|square| index::rook_slides(square, !empty))
+            );
+            assert_eq!(
+                BitBoard::bishop_attacks_setwise(pieces, empty),
+                union(pieces, &|square| index::bishop_slides(square, !empty))
+            );
+            assert_eq!(
+                BitBoard::queen_attacks_setwise(pieces, empty),
+                union(pieces, &|square| index::rook_slides(square, !empty)
+                    | index::bishop_slides(square, !empty))
+            );
+            assert_eq!(
+                BitBoard::knight_attacks_setwise(pieces),
+                union(pieces, &index::knight_attacks)
+            );
+            assert_eq!(
+                BitBoard::king_attacks_setwise(pieces),
+                union(pieces, &index::king_attacks)
+            );
+
+            for color in [Color::White, Color::Black] {
+                assert_eq!(
+                    BitBoard::pawn_attacks_setwise(pieces, color),
+                    union(pieces, &|square| index::pawn_attacks(square, color))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pawn_span_tests() {
+        let squares = |filter: &dyn Fn(Square) -> bool| {
+            Square::ALL
+                .into_iter()
+                .filter(|&square| filter(square))
+                .fold(BitBoard::EMPTY, |squares, square| squares | square.into())
+        };
+
+        for pawn in Square::ALL {
+            let pawn_bitboard = BitBoard::from(pawn);
+            let is_adjacent_file = |square: Square| square.file().abs_diff(pawn.file()) == 1;
+
+            assert_eq!(
+                pawn_bitboard.file_fill(),
+                squares(&|square| square.file() == pawn.file())
+            );
+            assert_eq!(pawn_bitboard.adjacent_files(), squares(&is_adjacent_file));
+
+            for color in [Color::White, Color::Black] {
+                let is_ahead = |square: Square| match color {
+                    Color::White => square.rank() > pawn.rank(),
+                    Color::Black => square.rank() < pawn.rank(),
+                };
+                let front_span =
+                    squares(&|square| square.file() == pawn.file() && is_ahead(square));
+
+                assert_eq!(pawn_bitboard.front_span(color), front_span);
+                assert_eq!(pawn_bitboard.rear_span(!color), front_span);
+                assert_eq!(
+                    pawn_bitboard.passed_pawn_mask(color),
+                    squares(&|square| is_ahead(square) && square.file().abs_diff(pawn.file()) <= 1)
+                );
+            }
+        }
+
+        // Spans of several pawns are the unions of their own spans
+        let pawns = BitBoard::from(Square::D4) | Square::E6.into() | Square::A2.into();
+
         assert_eq!(
-            Board::from_str(position_fen).unwrap().perft(depth),
-            expected_result
+            pawns.adjacent_files(),
+            squares(&|square| {
+                [
+                    Square::B_FILE,
+                    Square::C_FILE,
+                    Square::D_FILE,
+                    Square::E_FILE,
+                    Square::F_FILE,
+                ]
+                .contains(&square.file())
+            })
+        );
+        assert_eq!(
+            pawns.passed_pawn_mask(Color::White),
+            [Square::D4, Square::E6, Square::A2]
+                .into_iter()
+                .fold(BitBoard::EMPTY, |mask, pawn| mask
+                    | BitBoard::from(pawn).passed_pawn_mask(Color::White))
         );
     }
 
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn pext_slide_tests() {
+        if !index::pext::is_available() {
+            eprintln!("Skipping the PEXT slides, as the CPU doesn't support BMI2");
+            return;
+        }
+
+        for origin in Square::ALL {
+            let rook_reach = index::magic::rook_slides(origin, BitBoard::EMPTY);
+            let bishop_reach = index::magic::bishop_slides(origin, BitBoard::EMPTY);
+
+            // Blockers off the lines of the piece must make no difference either
+            for blockers in rook_reach.subsets() {
+                for blockers in [blockers, blockers | !rook_reach] {
+                    assert_eq!(
+                        // SAFETY: The CPU was checked to support BMI2
+                        unsafe { index::pext::rook_slides(origin, blockers) },
+                        index::magic::rook_slides(origin, blockers),
+                        "{origin} {blockers:?}"
+                    );
+                }
+            }
+
+            for blockers in bishop_reach.subsets() {
+                for blockers in [blockers, blockers | !bishop_reach] {
+                    assert_eq!(
+                        // SAFETY: The CPU was checked to support BMI2
+                        unsafe { index::pext::bishop_slides(origin, blockers) },
+                        index::magic::bishop_slides(origin, blockers),
+                        "{origin} {blockers:?}"
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     #[should_panic]
     fn invalid_make_move() {
@@ -92,4 +283,1645 @@ mod tests {
             .make_move(ChessMove::from_str("a1a1").unwrap())
             .unwrap();
     }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "starting position")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b KQkq c3 0 3"; "en passant test")]
+    #[test_case("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1"; "prevented castling")]
+    #[test_case("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1"; "promotion out of check")]
+    #[test_case("1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1"; "chess960 castling")]
+    #[test_case("5kr1/8/8/8/8/8/8/5KR1 w Kk - 0 1"; "chess960 king and rook swap")]
+    fn incremental_hash_tests(fen_string: &str) {
+        fn check_hashes(board: &Board, depth: u32) {
+            assert_eq!(
+                board.hash,
+                Board::from_str(&board.to_string()).unwrap().hash
+            );
+
+            if depth > 0 {
+                for (_, child_board) in board.gen_child_boards() {
+                    check_hashes(&child_board, depth - 1);
+                }
+            }
+        }
+
+        check_hashes(&Board::from_str(fen_string).unwrap(), 3);
+    }
+
+    #[test_case("8/8/8/4k3/8/8/8/4K3 w - - 0 1", true; "kings only")]
+    #[test_case("8/8/8/4k3/8/8/8/4KN2 w - - 0 1", true; "knight")]
+    #[test_case("8/8/8/4k3/8/8/8/4KB2 w - - 0 1", true; "bishop")]
+    #[test_case("8/8/4b3/4k3/8/8/8/4KB2 w - - 0 1", true; "same colored bishops")]
+    #[test_case("8/8/3b4/4k3/8/8/8/4KB2 w - - 0 1", false; "opposite colored bishops")]
+    #[test_case("8/8/8/4k3/8/8/8/3NKN2 w - - 0 1", false; "two knights")]
+    #[test_case("8/8/4n3/4k3/8/8/8/4KB2 w - - 0 1", false; "knight and bishop")]
+    #[test_case("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1", false; "pawn")]
+    #[test_case("8/8/8/4k3/8/8/8/4KR2 w - - 0 1", false; "rook")]
+    fn insufficient_material_tests(fen_string: &str, expected_result: bool) {
+        assert_eq!(
+            Board::from_str(fen_string)
+                .unwrap()
+                .has_insufficient_material(),
+            expected_result
+        );
+    }
+
+    fn play_moves(game: &mut Game, moves: &str) {
+        for chess_move in moves.split(' ') {
+            game.make_move(ChessMove::from_str(chess_move).unwrap())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn checkmate_outcome() {
+        let mut game = Game::starting_position();
+
+        play_moves(&mut game, "f2f3 e7e5 g2g4 d8h4");
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Color::Black)));
+    }
+
+    #[test]
+    fn stalemate_outcome() {
+        let game = Game::from_str("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw(DrawReason::Stalemate)));
+    }
+
+    #[test]
+    fn threefold_repetition_outcome() {
+        let mut game = Game::starting_position();
+
+        play_moves(&mut game, "g1f3 g8f6 f3g1 f6g8");
+
+        assert_eq!(game.repetitions(), 2);
+        assert_eq!(game.outcome(), None);
+
+        play_moves(&mut game, "g1f3 g8f6 f3g1 f6g8");
+
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::Draw(DrawReason::ThreefoldRepetition))
+        );
+        assert_eq!(game.moves().len(), 8);
+        assert_eq!(game.position_hashes().len(), 9);
+    }
+
+    #[test]
+    fn repetition_after_irreversible_move() {
+        let mut game = Game::starting_position();
+
+        play_moves(
+            &mut game,
+            "g1f3 g8f6 f3g1 f6g8 e2e4 e7e5 g1f3 g8f6 f3g1 f6g8",
+        );
+
+        assert_eq!(game.repetitions(), 2);
+        assert_eq!(game.outcome(), None);
+    }
+
+    #[test]
+    fn fifty_move_rule_outcome() {
+        let mut game = Game::from_str("8/8/8/4k3/8/8/8/R3K3 w - - 99 80").unwrap();
+
+        assert_eq!(game.outcome(), None);
+
+        play_moves(&mut game, "a1a2");
+
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::Draw(DrawReason::FiftyMoveRule))
+        );
+    }
+
+    #[test]
+    fn insufficient_material_outcome() {
+        let mut game = Game::from_str("8/8/8/4k3/8/8/3r4/4KN2 w - - 0 1").unwrap();
+
+        assert_eq!(game.outcome(), None);
+
+        play_moves(&mut game, "e1d2");
+
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::Draw(DrawReason::InsufficientMaterial))
+        );
+        assert!(!mg::gen_moves(game.board()).is_empty());
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "g1f3", "Nf3"; "knight move")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4", "e4"; "pawn push")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e1g1", "O-O"; "king-side castle")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e1c1", "O-O-O"; "queen-side castle")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "d5e6", "dxe6"; "pawn capture")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e5f7", "Nxf7"; "piece capture")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b KQkq c3 0 3", "d4c3", "dxc3"; "en passant")]
+    #[test_case("2r4r/5k2/p2p1p1P/Pp1P1P2/n4R1p/2pBK3/2P5/2N2R2 w - - 3 53", "f1f2", "R1f2"; "rank disambiguation 1")]
+    #[test_case("2r4r/5k2/p2p1p1P/Pp1P1P2/n4R1p/2pBK3/2P5/2N2R2 w - - 3 53", "f4f2", "R4f2"; "rank disambiguation 2")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "e3e1", "Ree1"; "file disambiguation 1")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "d2f3", "Ndf3"; "file disambiguation 2")]
+    #[test_case("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1", "a1b2", "Qa1b2"; "double disambiguation")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "e3e4", "Rxe4+"; "check")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "f7f8n", "f8=N+"; "under-promotion with check")]
+    #[test_case("1r1k1n2/3r4/1pbp3b/1p3p1p/1P1P1P2/1Rp3K1/2P1PBpN/5R2 b - - 1 44", "g2f1q", "gxf1=Q"; "capture with promotion")]
+    #[test_case("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2", "d8h4", "Qh4#"; "checkmate")]
+    #[test_case("6kr/8/8/8/8/8/8/6KR w Kk - 0 1", "g1h1", "O-O"; "chess960 castle")]
+    fn san_tests(fen_string: &str, chess_move: &str, san: &str) {
+        let board = Board::from_str(fen_string).unwrap();
+        let chess_move = ChessMove::from_str(chess_move).unwrap();
+
+        assert_eq!(board.move_to_san(chess_move), san);
+        assert_eq!(board.parse_san(san).unwrap(), chess_move);
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")]
+    #[test_case("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1")]
+    #[test_case("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10")]
+    fn circular_san_tests(fen_string: &str) {
+        let board = Board::from_str(fen_string).unwrap();
+
+        for chess_move in mg::gen_moves(&board) {
+            assert_eq!(
+                board.parse_san(&board.move_to_san(chess_move)).unwrap(),
+                chess_move
+            );
+        }
+    }
+
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "0-0-0", "e1c1"; "digit castling")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "Nxf7!?", "e5f7"; "annotated move")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "f8N", "f7f8n"; "promotion without equals sign")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "Rd3", "e3d3"; "missing check suffix")]
+    fn lenient_san_tests(fen_string: &str, san: &str, chess_move: &str) {
+        assert_eq!(
+            Board::from_str(fen_string).unwrap().parse_san(san).unwrap(),
+            ChessMove::from_str(chess_move).unwrap()
+        );
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ""; "empty")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "Nf9"; "invalid square")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "Zf3"; "invalid piece")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "Ng1hf3"; "invalid disambiguation")]
+    fn malformed_san_tests(fen_string: &str, san: &str) {
+        assert!(matches!(
+            Board::from_str(fen_string).unwrap().parse_san(san),
+            Err(ParseSanError::Malformed)
+        ));
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "Nd2"; "blocked target")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e5"; "unreachable target")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "O-O"; "unavailable castle")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "f8"; "missing promotion")]
+    fn illegal_san_tests(fen_string: &str, san: &str) {
+        assert!(matches!(
+            Board::from_str(fen_string).unwrap().parse_san(san),
+            Err(ParseSanError::IllegalMove)
+        ));
+    }
+
+    #[test_case("2r4r/5k2/p2p1p1P/Pp1P1P2/n4R1p/2pBK3/2P5/2N2R2 w - - 3 53", "Rf2"; "rooks on the same file")]
+    #[test_case("rn6/q4P2/4k3/pnp4p/PP1pbP1P/1K2R3/3N4/5RN1 w - - 11 48", "Nf3"; "knights")]
+    fn ambiguous_san_tests(fen_string: &str, san: &str) {
+        assert!(matches!(
+            Board::from_str(fen_string).unwrap().parse_san(san),
+            Err(ParseSanError::AmbiguousMove)
+        ));
+    }
+
+    const PGN_GAMES: &str = r#"[Event "Casual game"]
+[Site "?"]
+[White "Anderssen, \"Adolf\""]
+[Black "Kieseritzky"]
+[Result "1-0"]
+
+{The Immortal Game} 1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ $6 4. Kf1 b5 5. Bxb5 Nf6
+6. Nf3 Qh6 7. d3 Nh5 8. Nh4 Qg5 9. Nf5 c6 10. g4 Nf6 11. Rg1 cxb5 12. h4 Qg6
+13. h5 Qg5 14. Qf3 Ng8 15. Bxf4 Qf6 16. Nc3 Bc5 17. Nd5 Qxb2 18. Bd6 Bxg1
+(18... Qxa1+ 19. Ke2 Qb2 (19... Qxg1) 20. Kd2) 19. e5 Qxa1+ 20. Ke2 Na6
+21. Nxg7+ Kd8 22. Qf6+ Nxf6 23. Be7# {A beautiful finish} 1-0
+
+% An escaped line, which should be ignored
+[Event "Short draw"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"]
+
+30... Kd7 ; The king approaches
+31. Kd2 Kd6 $10 {Drawish} 1/2-1/2
+
+1. d4 d5 *
+"#;
+
+    #[test]
+    fn pgn_reading() {
+        let games = PgnReader::new(PGN_GAMES.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(games.len(), 3);
+
+        assert_eq!(games[0].tag("White"), Some("Anderssen, \"Adolf\""));
+        assert_eq!(
+            games[0].initial_comment.as_deref(),
+            Some("The Immortal Game")
+        );
+        assert_eq!(games[0].game.moves().len(), 45);
+        assert_eq!(games[0].annotations[5].nags, vec![6]);
+        assert_eq!(
+            games[0].annotations[44].comment.as_deref(),
+            Some("A beautiful finish")
+        );
+        assert_eq!(games[0].result, GameResult::Win(Color::White));
+        assert_eq!(games[0].game.outcome(), Some(Outcome::Win(Color::White)));
+
+        assert_eq!(games[1].game.moves().len(), 3);
+        assert_eq!(
+            games[1].annotations[0].comment.as_deref(),
+            Some("The king approaches")
+        );
+        assert_eq!(
+            games[1].annotations[2],
+            MoveAnnotation {
+                comment: Some("Drawish".to_string()),
+                nags: vec![10],
+            }
+        );
+        assert_eq!(games[1].result, GameResult::Draw);
+
+        assert!(games[2].tags.is_empty());
+        assert_eq!(games[2].game.moves().len(), 2);
+        assert_eq!(games[2].result, GameResult::Unknown);
+    }
+
+    #[test]
+    fn pgn_error_recovery() {
+        let pgn = "[Event \"First\"]\n\n1. e4 e5 1-0\n\n\
+            [Event \"Second\"]\n\n1. e4 e5 2. Ke3 Nf6 (2... Nc6) 0-1\n\n\
+            [Event \"Third\"]\n[FEN \"8/8/8 w - - 0 1\"]\n\n1. e4 *\n\n\
+            [Event \"Fourth\"]\n\n1. d4 (1. e4 *\n";
+
+        let games = PgnReader::new(pgn.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(games.len(), 4);
+        assert_eq!(games[0].as_ref().unwrap().tag("Event"), Some("First"));
+
+        let error = games[1].as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (7, 13));
+        assert!(matches!(
+            &error.kind,
+            PgnErrorKind::InvalidMove(chess_move, ParseSanError::IllegalMove) if chess_move == "Ke3"
+        ));
+
+        let error = games[2].as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (10, 2));
+        assert!(matches!(error.kind, PgnErrorKind::InvalidFen(_)));
+
+        let error = games[3].as_ref().unwrap_err();
+        assert!(matches!(&error.kind, PgnErrorKind::UnexpectedToken(token) if token == "*"));
+    }
+
+    #[test]
+    fn pgn_error_recovery_without_result() {
+        let pgn = "[Event \"First\"]\n\n1. e4 e5 2. Qxf7\n\n\
+            [Event \"Second\"]\n\n1. d4 d5 1/2-1/2\n";
+
+        let games = PgnReader::new(pgn.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(games.len(), 2);
+
+        let error = games[0].as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (3, 13));
+
+        let game = games[1].as_ref().unwrap();
+        assert_eq!(game.tag("Event"), Some("Second"));
+        assert_eq!(game.game.moves().len(), 2);
+    }
+
+    #[test]
+    fn pgn_variations() {
+        let read = |movetext: &str| PgnReader::new(movetext.as_bytes()).next().unwrap();
+
+        let game =
+            read("1. e4 e5 (1... c5 2. Nf3 (2. c3 d5) 2... d6 {Najdorf} $1) 2. Nf3 *").unwrap();
+        assert_eq!(game.game.moves().len(), 3);
+        assert_eq!(game.annotations[1], MoveAnnotation::default());
+
+        let error = read("1. e4 e5 (1... c5 2. Nf3 (2. Ke7)) 2. Nf3 *").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 30));
+        assert!(matches!(
+            &error.kind,
+            PgnErrorKind::InvalidMove(chess_move, ParseSanError::IllegalMove) if chess_move == "Ke7"
+        ));
+
+        let error = read("(1. d4) 1. e4 *").unwrap_err();
+        assert!(matches!(&error.kind, PgnErrorKind::UnexpectedToken(token) if token == "("));
+    }
+
+    #[test]
+    fn pgn_writing() {
+        let mut game = Game::starting_position();
+        play_moves(&mut game, "f2f3 e7e5 g2g4 d8h4");
+
+        let mut pgn_game = PgnGame::from(game);
+        pgn_game.annotations[1].comment = Some("Threatening {mate} now".to_string());
+        pgn_game.annotations[2].nags.push(4);
+
+        assert_eq!(
+            pgn_game.to_string(),
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+            [White \"?\"]\n[Black \"?\"]\n[Result \"0-1\"]\n\n\
+            1. f3 e5 {Threatening {mate} {now} 2. g4 $4 Qh4# 0-1"
+        );
+
+        let read = PgnReader::new(pgn_game.to_string().as_bytes())
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            read.annotations[1].comment.as_deref(),
+            Some("Threatening {mate now")
+        );
+    }
+
+    #[test]
+    fn pgn_round_trip() {
+        let games = PgnReader::new(PGN_GAMES.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut writer = PgnWriter::new(vec![]);
+
+        for game in &games {
+            writer.write_game(game).unwrap();
+        }
+
+        let written = writer.into_inner();
+        let read_games = PgnReader::new(&written[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(read_games.len(), games.len());
+
+        for (read_game, game) in read_games.iter().zip(&games) {
+            assert_eq!(read_game.tags, game.tags);
+            assert_eq!(read_game.game.starting_board(), game.game.starting_board());
+            assert_eq!(read_game.game.moves(), game.game.moves());
+            assert_eq!(read_game.initial_comment, game.initial_comment);
+            assert_eq!(read_game.annotations, game.annotations);
+            assert_eq!(read_game.result, game.result);
+        }
+
+        assert!(String::from_utf8(written)
+            .unwrap()
+            .lines()
+            .all(|line| line.len() <= 80));
+    }
+
+    #[test]
+    fn pgn_chess960() {
+        let pgn = "[Variant \"Chess960\"]\n\
+            [FEN \"1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1\"]\n\n1. O-O O-O-O *";
+        let pgn_game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+
+        assert_eq!(
+            pgn_game.game.starting_board().castling_mode,
+            CastlingMode::Chess960
+        );
+        assert_eq!(
+            pgn_game.game.moves(),
+            [
+                ChessMove::from_str("e1g1").unwrap(),
+                ChessMove::from_str("e8b8").unwrap()
+            ]
+        );
+
+        let written = PgnGame::from(pgn_game.game).to_string();
+        assert!(written.contains("[Variant \"Chess960\"]"));
+        assert!(written.contains("1. O-O O-O-O"));
+    }
+
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"; "shredder-fen")]
+    #[test_case("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1", "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w KQkq - 0 1"; "shredder-fen with inner rooks")]
+    #[test_case("rk2r2r/8/8/8/8/8/8/RK2R2R w Ea - 0 1", "rk2r2r/8/8/8/8/8/8/RK2R2R w Eq - 0 1"; "shredder-fen with an ambiguous rook")]
+    #[test_case("rk2r2r/8/8/8/8/8/8/RK2R2R w Eq - 0 1", "rk2r2r/8/8/8/8/8/8/RK2R2R w Eq - 0 1"; "x-fen with an ambiguous rook")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "shredder-fen of the starting position")]
+    fn chess960_fen_tests(fen_string: &str, x_fen_string: &str) {
+        let board = Board::from_str(fen_string).unwrap();
+
+        assert_eq!(board.to_string(), x_fen_string);
+        assert_eq!(
+            Board::from_str(&board.to_shredder_fen()).unwrap().hash,
+            board.hash
+        );
+    }
+
+    #[test_case("rk2r2r/8/8/8/8/8/8/RK2R2R w EE - 0 1"; "repeated file")]
+    #[test_case("rk2r2r/8/8/8/8/8/8/RK2R2R w EH - 0 1"; "two rooks on one side")]
+    #[test_case("rk2r2r/8/8/8/8/8/8/RK2R2R w C - 0 1"; "file without rook")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w K - 0 1"; "missing king-side rook")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w Kk - 0 1"; "king off the back rank")]
+    fn invalid_castling_rights_tests(fen_string: &str) {
+        assert!(matches!(
+            Board::from_str(fen_string),
+            Err(ParseBoardError::InvalidCastlingRights)
+        ));
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "starting position")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b KQkq c3 0 3"; "en passant")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"; "chess960")]
+    #[test_case("8/2k5/8/8/8/8/5K2/8 b - - 57 80"; "clocks")]
+    fn builder_round_trip_tests(fen_string: &str) {
+        let board = Board::from_str(fen_string).unwrap();
+        let built = BoardBuilder::from(board).build().unwrap();
+
+        assert_eq!(built.to_string(), board.to_string());
+        assert_eq!(built.hash, board.hash);
+        assert_eq!(built.castling_mode, board.castling_mode);
+    }
+
+    #[test]
+    fn builder_tests() {
+        let white = |kind| Piece {
+            kind,
+            color: Color::White,
+        };
+        let black = |kind| Piece {
+            kind,
+            color: Color::Black,
+        };
+
+        let builder = BoardBuilder::new()
+            .place(Square::E1, white(PieceKind::King))
+            .place(Square::A1, white(PieceKind::Rook))
+            .place(Square::H1, white(PieceKind::Rook))
+            .place(Square::E8, black(PieceKind::King))
+            .place(Square::D7, black(PieceKind::Pawn))
+            .castling_right(Color::White, CastlingSide::QueenSide)
+            .castling_rook(Color::White, Square::H1)
+            .playing_color(Color::Black);
+
+        assert_eq!(builder.piece(Square::D7), Some(black(PieceKind::Pawn)));
+        assert_eq!(builder.piece(Square::D6), None);
+        assert_eq!(
+            builder.build().unwrap().to_string(),
+            "4k3/3p4/8/8/8/8/8/R3K2R b KQ - 0 1"
+        );
+
+        let mut board = builder.build().unwrap();
+        board
+            .make_move(ChessMove::from_str("d7d5").unwrap())
+            .unwrap();
+
+        let moved = builder
+            .clone()
+            .remove(Square::D7)
+            .place(Square::D5, black(PieceKind::Pawn))
+            .playing_color(Color::White)
+            .en_passant_capture_square(None)
+            .ply_clock(0)
+            .full_moves(2);
+        assert_eq!(moved.build().unwrap().hash, board.hash);
+
+        let replaced = builder.clone().place(Square::H1, white(PieceKind::Queen));
+        assert!(matches!(
+            replaced.build(),
+            Err(ParseBoardError::InvalidCastlingRights)
+        ));
+        assert!(replaced.clear_castling_rights().build().is_ok());
+
+        for (builder, expected) in [
+            (
+                builder.clone().remove(Square::E8),
+                ParseBoardError::InvalidKingCount,
+            ),
+            (
+                builder.clone().place(Square::D1, black(PieceKind::Pawn)),
+                ParseBoardError::PawnsOnEdgeRanks,
+            ),
+            (
+                builder.clone().place(Square::E2, black(PieceKind::Queen)),
+                ParseBoardError::CapturableKing,
+            ),
+            (
+                builder.clone().en_passant_capture_square(Some(Square::D3)),
+                ParseBoardError::InvalidEnPassantSquare(None),
+            ),
+            (
+                builder.clone().en_passant_capture_square(Some(Square::H8)),
+                ParseBoardError::InvalidEnPassantSquare(None),
+            ),
+            (
+                builder
+                    .clone()
+                    .playing_color(Color::White)
+                    .en_passant_capture_square(Some(Square::A1)),
+                ParseBoardError::InvalidEnPassantSquare(None),
+            ),
+            (
+                builder.clone().ply_clock(3),
+                ParseBoardError::IllegalHalfMoveClock,
+            ),
+            (
+                builder
+                    .clone()
+                    .castling_right(Color::White, CastlingSide::KingSide),
+                ParseBoardError::InvalidCastlingRights,
+            ),
+        ] {
+            assert_eq!(
+                builder.build().unwrap_err().to_string(),
+                expected.to_string()
+            );
+        }
+    }
+
+    #[test_case(Color::White, Square::A1; "white on first rank")]
+    #[test_case(Color::White, Square::H1; "white on last file")]
+    #[test_case(Color::Black, Square::H8; "black on eighth rank")]
+    #[test_case(Color::Black, Square::A8; "black on first file")]
+    fn from_players_edge_en_passant_tests(color: Color, square: Square) {
+        let board = Board::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+        assert!(matches!(
+            Board::from_players(
+                board.us,
+                board.them,
+                color,
+                Some(square),
+                0,
+                1,
+                CastlingMode::Standard,
+            ),
+            Err(ParseBoardError::InvalidEnPassantSquare(None))
+        ));
+    }
+
+    #[test_case("KQvK", Color::White, CheckConstraint::NotInCheck, false; "queen")]
+    #[test_case("KRBvKN", Color::Black, CheckConstraint::InCheck, false; "in check")]
+    #[test_case("KPPPvKPP", Color::White, CheckConstraint::Any, false; "pawns")]
+    #[test_case("KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP", Color::Black, CheckConstraint::NotInCheck, true; "full material")]
+    #[test_case("KRvKR", Color::White, CheckConstraint::Any, true; "castling")]
+    fn random_position_tests(
+        material: &str,
+        playing_color: Color,
+        check: CheckConstraint,
+        castling: bool,
+    ) {
+        let options = RandomPositionOptions {
+            material: material.parse().unwrap(),
+            playing_color: Some(playing_color),
+            check,
+            castling,
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..100 {
+            let board = random_position(&mut rng, &options).unwrap();
+            let fen = board.to_string();
+
+            assert_eq!(board.playing_color, playing_color, "{fen}");
+            assert_eq!(Board::from_str(&fen).unwrap().hash, board.hash, "{fen}");
+
+            match check {
+                CheckConstraint::Any => {}
+                CheckConstraint::InCheck => assert!(board.in_check(), "{fen}"),
+                CheckConstraint::NotInCheck => assert!(!board.in_check(), "{fen}"),
+            }
+
+            for color in [Color::White, Color::Black] {
+                let player = board.player(color);
+
+                for kind in PieceKind::ALL {
+                    let expected = options
+                        .material
+                        .side(color)
+                        .iter()
+                        .filter(|&&other| other == kind)
+                        .count()
+                        + (kind == PieceKind::King) as usize;
+
+                    assert_eq!(
+                        player.piece_bitboard(kind).count_ones() as usize,
+                        expected,
+                        "{fen}"
+                    );
+                }
+
+                assert_eq!(player.can_castle_king_side(), castling, "{fen}");
+            }
+        }
+
+        assert_eq!(
+            random_position(&mut StdRng::seed_from_u64(1), &options)
+                .unwrap()
+                .to_string(),
+            random_position(&mut StdRng::seed_from_u64(1), &options)
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn random_position_errors() {
+        let options = |material: &str| RandomPositionOptions {
+            material: material.parse().unwrap(),
+            ..RandomPositionOptions::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        assert!(matches!(
+            random_position(&mut rng, &options("KPPPPPPPPPvK")),
+            Err(RandomPositionError::TooManyPieces)
+        ));
+        assert!(matches!(
+            random_position(
+                &mut rng,
+                &RandomPositionOptions {
+                    check: CheckConstraint::InCheck,
+                    ..options("KvK")
+                }
+            ),
+            Err(RandomPositionError::NotFound)
+        ));
+
+        assert_eq!(options("KBNvKP").material.to_string(), "KBNvKP");
+        assert_eq!(options("KNPBvKP").material.to_string(), "KBNPvKP");
+        assert_eq!(
+            MaterialSignature::of_board(
+                &Board::from_str("4k3/p7/8/8/8/8/8/NB2K2Q w - - 0 1").unwrap()
+            ),
+            "KQBNvKP".parse().unwrap()
+        );
+
+        for (material, expected) in [
+            ("KQK", ParseMaterialSignatureError::MissingSeparator),
+            ("QvK", ParseMaterialSignatureError::InvalidKings),
+            ("KKvK", ParseMaterialSignatureError::InvalidKings),
+            ("KqvK", ParseMaterialSignatureError::InvalidPiece),
+            ("KXvK", ParseMaterialSignatureError::InvalidPiece),
+        ] {
+            assert_eq!(
+                material
+                    .parse::<MaterialSignature>()
+                    .unwrap_err()
+                    .to_string(),
+                expected.to_string()
+            );
+        }
+    }
+
+    #[test_case("1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1", "e1g1", "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R3RK1 b kq - 1 1"; "king-side")]
+    #[test_case("1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1", "e1b1", "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/2KR2R1 b kq - 1 1"; "queen-side")]
+    #[test_case("6kr/8/8/8/8/8/8/6KR w Kk - 0 1", "g1h1", "6kr/8/8/8/8/8/8/5RK1 b k - 1 1"; "unmoved king")]
+    #[test_case("5kr1/6p1/8/8/8/8/6P1/5KR1 w Kk - 0 1", "f1g1", "5kr1/6p1/8/8/8/8/6P1/5RK1 b k - 1 1"; "king and rook swap")]
+    #[test_case("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8a8", "2kr3r/8/8/8/8/8/8/R3K2R w KQ - 1 2"; "standard position")]
+    fn chess960_castling_tests(fen_string: &str, chess_move: &str, result_fen_string: &str) {
+        let mut original_board = Board::from_str(fen_string).unwrap();
+        original_board.castling_mode = CastlingMode::Chess960;
+
+        let chess_move = ChessMove::from_str(chess_move).unwrap();
+        let mut board = original_board;
+        let mut in_place_board = original_board;
+
+        board.make_move(chess_move).unwrap();
+        assert_eq!(board.to_string(), result_fen_string);
+
+        // SAFETY: The move was checked to be legal above
+        let undo = unsafe { in_place_board.make_move_unchecked(chess_move) };
+        assert_eq!(in_place_board, board);
+
+        unsafe { in_place_board.unmake_move(chess_move, undo) };
+        assert_eq!(in_place_board, original_board);
+    }
+
+    #[test]
+    fn castling_modes() {
+        let mut board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.castling_mode, CastlingMode::Standard);
+        assert!(board
+            .clone()
+            .make_move(ChessMove::from_str("e1g1").unwrap())
+            .is_ok());
+        assert!(board
+            .clone()
+            .make_move(ChessMove::from_str("e1h1").unwrap())
+            .is_err());
+
+        board.castling_mode = CastlingMode::Chess960;
+        assert!(board
+            .clone()
+            .make_move(ChessMove::from_str("e1g1").unwrap())
+            .is_err());
+        assert!(board
+            .clone()
+            .make_move(ChessMove::from_str("e1h1").unwrap())
+            .is_ok());
+
+        let board = Board::from_str("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w KQkq - 0 1").unwrap();
+        assert_eq!(board.castling_mode, CastlingMode::Chess960);
+    }
+
+    #[test]
+    fn chess960_castling_shielded_by_rook() {
+        // After castling queen-side the king would be on c1, which is attacked by the rook on a1
+        // once the castling rook leaves b1
+        let board = Board::from_str("8/8/8/8/8/8/8/rR1K1k2 w B - 0 1").unwrap();
+
+        assert!(!mg::gen_moves(&board).contains(&ChessMove::from_str("d1b1").unwrap()));
+    }
+
+    fn squares(squares: &str) -> BitBoard {
+        squares
+            .split_whitespace()
+            .map(|square| BitBoard::from(Square::from_str(square).unwrap()))
+            .fold(BitBoard::EMPTY, |squares, square| squares | square)
+    }
+
+    #[test_case("d5", "", "e4 c3 d2 f6 a8"; "direct attackers")]
+    #[test_case("d5", "d2", "e4 c3 d1 f6 a8"; "x-ray through rook")]
+    #[test_case("d5", "e4 c3 d2 d1", "f6 a8"; "removed attackers")]
+    #[test_case("e2", "", "d1 e1 d2 c3 f1"; "attackers of both colors")]
+    fn attackers_to_tests(square: &str, removed: &str, attackers: &str) {
+        let board = Board::from_str("b3k3/8/5n2/3p4/4P3/2N5/3R4/3QKb2 w - - 0 1").unwrap();
+        let occupation = board.occupation() & !squares(removed);
+
+        assert_eq!(
+            board.attackers_to(Square::from_str(square).unwrap(), occupation),
+            squares(attackers)
+        );
+    }
+
+    #[test_case("b3k3/8/5n2/3p4/4P3/2N5/3R4/3QKb2 w - - 0 1"; "pieces of both colors")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1"; "kiwipete")]
+    fn is_attacked_tests(fen_string: &str) {
+        let board = Board::from_str(fen_string).unwrap();
+
+        for square in Square::ALL {
+            let them = board.occupation() & !board.us.king;
+            let us = board.occupation() & !board.them.king;
+
+            assert_eq!(
+                board.is_attacked_by_them(square),
+                !(board.attackers_to(square, them) & board.them.occupation).is_empty()
+            );
+            assert_eq!(
+                board.is_attacked_by_us(square),
+                !(board.attackers_to(square, us) & board.us.occupation).is_empty()
+            );
+        }
+    }
+
+    #[test_case(Color::White, "a3 c3 d1 d2 e2 f2 f1 g1 e1 h2 h3 h4 h5 h6 h7"; "white")]
+    #[test_case(Color::Black, "g6 d8 d7 e7 f7 f8"; "black")]
+    fn attacked_squares_tests(color: Color, attacked: &str) {
+        let board = Board::from_str("4k3/7p/8/8/8/8/1P6/4K2R b - - 0 1").unwrap();
+
+        assert_eq!(board.attacked_squares(color), squares(attacked));
+    }
+
+    // Values from a widely used SEE test corpus, which counts knights and bishops as 325 and
+    // queens as 1000
+    #[test_case("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5", 100; "rook takes defended pawn")]
+    #[test_case("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5", -225; "knight takes defended pawn")]
+    #[test_case("4R3/2r3p1/5bk1/1p1r3p/p2PR1P1/P1BK1P2/1P6/8 b - - 0 1", "h5g4", 0; "pawn exchange")]
+    #[test_case("4R3/2r3p1/5bk1/1p1r1p1p/p2PR1P1/P1BK1P2/1P6/8 b - - 0 1", "h5g4", 0; "pawn exchange with support")]
+    #[test_case("4r1k1/5pp1/nbp4p/1p2p2q/1P2P1b1/1BP2N1P/1B2QPPK/3R4 b - - 0 1", "g4f3", 0; "bishop takes knight")]
+    #[test_case("2r1r1k1/pp1bppbp/3p1np1/q3P3/2P2P2/1P2B3/P1N1B1PP/2RQ1RK1 b - - 0 1", "d6e5", 100; "pawn takes pawn")]
+    #[test_case("7r/5qpk/p1Qp1b1p/3r3n/BB3p2/5p2/P1P2P2/4RK1R w - - 0 1", "e1e8", 0; "rook to empty square")]
+    #[test_case("6rr/6pk/p1Qp1b1p/2n5/1B3p2/5p2/P1P2P2/4RK1R w - - 0 1", "e1e8", -500; "rook hangs")]
+    #[test_case("7r/5qpk/2Qp1b1p/1N1r3n/BB3p2/5p2/P1P2P2/4RK1R w - - 0 1", "e1e8", -500; "rook hangs with x-ray")]
+    #[test_case("6RR/4bP2/8/8/5r2/3K4/5p2/4k3 w - - 0 1", "f7f8q", 225; "queen promotion")]
+    #[test_case("6RR/4bP2/8/8/5r2/3K4/5p2/4k3 w - - 0 1", "f7f8n", 225; "knight promotion")]
+    #[test_case("7R/5P2/8/8/6r1/3K4/5p2/4k3 w - - 0 1", "f7f8q", 900; "undefended queen promotion")]
+    #[test_case("7R/5P2/8/8/6r1/3K4/5p2/4k3 w - - 0 1", "f7f8b", 225; "undefended bishop promotion")]
+    #[test_case("7R/4bP2/8/8/1q6/3K4/5p2/4k3 w - - 0 1", "f7f8r", -100; "rook promotion")]
+    #[test_case("8/4kp2/2npp3/1Nn5/1p2PQP1/7q/1PP1B3/4KR1r b - - 0 1", "h1f1", 0; "rook takes rook")]
+    #[test_case("8/4kp2/2npp3/1Nn5/1p2P1P1/7q/1PP1B3/4KR1r b - - 0 1", "h1f1", 0; "rook takes rook without queen")]
+    #[test_case("2r2r1k/6bp/p7/2q2p1Q/3PpP2/1B6/P5PP/2RR3K b - - 0 1", "c5c1", 0; "queen takes rook")]
+    #[test_case("r2qk1nr/pp2ppbp/2b3p1/2p1p3/8/2N2N2/PPPP1PPP/R1BQR1K1 w kq - 0 1", "f3e5", 100; "knight takes pawn")]
+    #[test_case("6r1/4kq2/b2p1p2/p1pPb3/p1P2B1Q/2P4P/2B1R1P1/6K1 w - - 0 1", "f4e5", 0; "bishop takes bishop")]
+    #[test_case("3q2nk/pb1r1p2/np6/3P2Pp/2p1P3/2R4B/PQ3P1P/3R2K1 w - h6 0 1", "g5h6", 0; "en passant")]
+    #[test_case("3q2nk/pb1r1p2/np6/3P2Pp/2p1P3/2R1B2B/PQ3P1P/3R2K1 w - h6 0 1", "g5h6", 100; "supported en passant")]
+    #[test_case("2r4r/1P4pk/p2p1b1p/7n/BB3p2/2R2p2/P1P2P2/4RK2 w - - 0 1", "c3c8", 500; "rook takes rook")]
+    #[test_case("2r5/1P4pk/p2p1b1p/5b1n/BB3p2/2R2p2/P1P2P2/4RK2 w - - 0 1", "c3c8", 500; "rook takes rook with promotion")]
+    #[test_case("2r4k/2r4p/p7/2b2p1b/4pP2/1BR5/P1R3PP/2Q4K w - - 0 1", "c3c5", 325; "rook takes bishop")]
+    #[test_case("8/pp6/2pkp3/4bp2/2R3b1/2P5/PP4B1/1K6 w - - 0 1", "g2c6", -225; "bishop takes pawn")]
+    #[test_case("3r3k/3r4/2n1n3/8/3p4/2PR4/1B1Q4/3R3K w - - 0 1", "d3d4", -175; "long exchange")]
+    fn see_tests(fen_string: &str, chess_move: &str, see: i32) {
+        let board = Board::from_str(fen_string).unwrap();
+        let chess_move = ChessMove::from_str(chess_move).unwrap();
+        let values = PieceValues {
+            knight: 325,
+            bishop: 325,
+            queen: 1000,
+            ..PieceValues::default()
+        };
+
+        assert!(mg::gen_moves(&board).contains(&chess_move));
+        assert_eq!(board.see_with(chess_move, &values), see);
+        assert!(board.see_ge_with(chess_move, see, &values));
+        assert!(!board.see_ge_with(chess_move, see + 1, &values));
+    }
+
+    #[test]
+    fn see_ge_random_tests() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..200 {
+            let mut board = Board::starting_position();
+
+            for _ in 0..rng.gen_range(0..80) {
+                let moves = mg::gen_moves(&board);
+
+                for &chess_move in &moves {
+                    let see = board.see(chess_move);
+
+                    for threshold in [see - 1, see, see + 1, -900, -300, -100, 0, 100, 300, 900] {
+                        assert_eq!(
+                            board.see_ge(chess_move, threshold),
+                            see >= threshold,
+                            "{chess_move} with threshold {threshold} on {board}"
+                        );
+                    }
+                }
+
+                let Some(&chess_move) = moves.choose(&mut rng) else {
+                    break;
+                };
+
+                board.make_move(chess_move).unwrap();
+            }
+        }
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "starting position")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1"; "en passant move 1")]
+    #[test_case("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1"; "en passant move 2")]
+    #[test_case("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1"; "en passant move with check")]
+    #[test_case("5k2/8/8/8/8/8/8/4K2R w K - 0 1"; "king-side castle with check")]
+    #[test_case("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1"; "queen-side castle with check")]
+    #[test_case("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1"; "castle rights")]
+    #[test_case("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1"; "prevented castling")]
+    #[test_case("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1"; "promotion out of check")]
+    #[test_case("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1"; "discovered check")]
+    #[test_case("4k3/1P6/8/8/8/8/K7/8 w - - 0 1"; "promotion with check")]
+    #[test_case("8/P1k5/K7/8/8/8/8/8 w - - 0 1"; "under-promotion with check")]
+    #[test_case("K1k5/8/P7/8/8/8/8/8 w - - 0 1"; "self stalemate")]
+    #[test_case("8/k1P5/8/1K6/8/8/8/8 w - - 0 1"; "stalemate and checkmate 1")]
+    #[test_case("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1"; "stalemate and checkmate 2")]
+    #[test_case("r6r/1b2k1bq/8/8/7B/8/8/R3K2R b KQ - 3 2"; "misc 1")]
+    #[test_case("8/8/8/2k5/2pP4/8/B7/4K3 b - d3 0 3"; "misc 2")]
+    #[test_case("r1bqkbnr/pppppppp/n7/8/8/P7/1PPPPPPP/RNBQKBNR w KQkq - 2 2"; "misc 3")]
+    #[test_case("r3k2r/p1pp1pb1/bn2Qnp1/2qPN3/1p2P3/2N5/PPPBBPPP/R3K2R b KQkq - 3 2"; "misc 4")]
+    #[test_case("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"; "misc 5")]
+    #[test_case("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"; "misc 6")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"; "chess960 1")]
+    #[test_case("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9"; "chess960 2")]
+    #[test_case("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9"; "chess960 3")]
+    #[test_case("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9"; "chess960 4")]
+    #[test_case("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9"; "chess960 5")]
+    #[test_case("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9"; "chess960 6")]
+    #[test_case("q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9"; "chess960 7")]
+    #[test_case("qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9"; "chess960 8")]
+    #[test_case("qnnbbrkr/1p2ppp1/2pp3p/p7/1P5P/2NP4/P1P1PPP1/Q1NBBRKR w HFhf - 0 9"; "chess960 9")]
+    #[test_case("qn1rbbkr/ppp2p1p/1n1pp1p1/8/3P4/P6P/1PP1PPPK/QNNRBB1R w hd - 2 9"; "chess960 10")]
+    fn staged_move_generation_tests(fen_string: &str) {
+        fn check_stages(board: &Board, depth: u32) {
+            let moves = mg::gen_moves(board);
+
+            let mut captures = vec![];
+            let mut quiets = vec![];
+            let mut quiet_checks = vec![];
+
+            mg::gen_captures(board, &mut captures);
+            mg::gen_quiets(board, &mut quiets);
+            mg::gen_quiet_checks(board, &mut quiet_checks);
+
+            assert_eq!(captures.len() + quiets.len(), moves.len());
+            assert!(captures
+                .iter()
+                .chain(&quiets)
+                .all(|chess_move| moves.contains(chess_move)));
+
+            for chess_move in &captures {
+                assert!(
+                    chess_move.promotion.is_some()
+                        || board.piece_kind_board[chess_move.target].is_some()
+                        || Some(chess_move.target) == board.en_passant_capture_square
+                );
+            }
+
+            assert_eq!(
+                quiet_checks,
+                quiets
+                    .iter()
+                    .copied()
+                    .filter(|&chess_move| {
+                        let mut child_board = *board;
+                        child_board.make_move(chess_move).unwrap();
+                        child_board.in_check()
+                    })
+                    .collect::<Vec<_>>()
+            );
+
+            if depth > 0 {
+                for (_, child_board) in board.gen_child_boards() {
+                    check_stages(&child_board, depth - 1);
+                }
+            }
+        }
+
+        check_stages(&Board::from_str(fen_string).unwrap(), 2);
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4", MoveKind::Quiet, PieceKind::Pawn, None, false; "double push")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e5f7", MoveKind::Capture, PieceKind::Knight, Some(PieceKind::Pawn), false; "capture")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b KQkq c3 0 3", "d4c3", MoveKind::EnPassant, PieceKind::Pawn, Some(PieceKind::Pawn), false; "en passant")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e1g1", MoveKind::Castle(CastlingSide::KingSide), PieceKind::King, None, false; "king side castle")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e1c1", MoveKind::Castle(CastlingSide::QueenSide), PieceKind::King, None, false; "queen side castle")]
+    #[test_case("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1", "e1g1", MoveKind::Castle(CastlingSide::KingSide), PieceKind::King, None, false; "chess960 king side castle")]
+    #[test_case("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1", "e1b1", MoveKind::Castle(CastlingSide::QueenSide), PieceKind::King, None, false; "chess960 queen side castle")]
+    #[test_case("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", MoveKind::Castle(CastlingSide::KingSide), PieceKind::King, None, true; "castle with check")]
+    #[test_case("8/P7/8/8/8/8/8/k1K5 w - - 0 1", "a7a8q", MoveKind::Promotion(PieceKind::Queen), PieceKind::Pawn, None, true; "promotion")]
+    #[test_case("1r6/P7/8/8/8/8/8/k1K5 w - - 0 1", "a7b8n", MoveKind::CapturePromotion(PieceKind::Knight), PieceKind::Pawn, Some(PieceKind::Rook), false; "capture with promotion")]
+    #[test_case("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1", "e2c3", MoveKind::Quiet, PieceKind::Knight, None, true; "discovered check")]
+    #[test_case("8/8/8/R2pP2k/8/8/8/4K3 w - d6 0 1", "e5d6", MoveKind::EnPassant, PieceKind::Pawn, Some(PieceKind::Pawn), true; "en passant discovered check")]
+    fn move_classification_tests(
+        fen_string: &str,
+        chess_move: &str,
+        kind: MoveKind,
+        moved_piece_kind: PieceKind,
+        captured_piece_kind: Option<PieceKind>,
+        gives_check: bool,
+    ) {
+        let board = Board::from_str(fen_string).unwrap();
+        let chess_move = ChessMove::from_str(chess_move).unwrap();
+        assert!(mg::gen_moves(&board).contains(&chess_move));
+
+        assert_eq!(board.classify(chess_move), kind);
+        assert_eq!(board.is_capture(chess_move), captured_piece_kind.is_some());
+        assert_eq!(
+            board.moved_piece(chess_move),
+            Some(Piece {
+                kind: moved_piece_kind,
+                color: board.playing_color,
+            })
+        );
+        assert_eq!(
+            board.captured_piece(chess_move),
+            captured_piece_kind.map(|kind| Piece {
+                kind,
+                color: !board.playing_color,
+            })
+        );
+        assert_eq!(board.gives_check(chess_move), gives_check);
+
+        let mut child_board = board;
+        child_board.make_move(chess_move).unwrap();
+
+        assert_eq!(child_board.in_check(), gives_check);
+        assert_eq!(
+            child_board.us.occupation.count_ones() < board.them.occupation.count_ones(),
+            captured_piece_kind.is_some()
+        );
+    }
+
+    #[test]
+    fn legality_check_tests() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let starting_boards = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b KQkq c3 0 3",
+            "8/7r/4Rn1p/1bP1P3/1P1kq3/2N1r3/p1p5/K3b3 w - - 0 1",
+            "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+        ]
+        .map(|fen_string| Board::from_str(fen_string).unwrap());
+        let promotions = [
+            None,
+            Some(PieceKind::Queen),
+            Some(PieceKind::Rook),
+            Some(PieceKind::Bishop),
+            Some(PieceKind::Knight),
+            Some(PieceKind::King),
+        ];
+
+        for _ in 0..300 {
+            let mut board = *starting_boards.choose(&mut rng).unwrap();
+
+            for _ in 0..rng.gen_range(0..60) {
+                let Some(&chess_move) = mg::gen_moves(&board).choose(&mut rng) else {
+                    break;
+                };
+
+                board.make_move(chess_move).unwrap();
+            }
+
+            let moves = mg::gen_moves(&board);
+
+            for origin in Square::ALL {
+                for target in Square::ALL {
+                    for promotion in promotions {
+                        let chess_move = ChessMove {
+                            origin,
+                            target,
+                            promotion,
+                        };
+                        let is_legal = moves.contains(&chess_move);
+
+                        assert_eq!(board.is_legal(chess_move), is_legal, "{board} {chess_move}");
+                        assert!(!is_legal || board.is_pseudo_legal(chess_move));
+                    }
+                }
+            }
+        }
+    }
+
+    // Plays random games from a few positions, randomly taking back moves along the way, and checks
+    // the invariants of the board after every move made or taken back.
+    fn invariant_random_walk(games: usize) {
+        let mut rng = StdRng::seed_from_u64(0);
+        let starting_boards = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ]
+        .map(|fen_string| Board::from_str(fen_string).unwrap());
+
+        for _ in 0..games {
+            let mut board = *starting_boards.choose(&mut rng).unwrap();
+            let mut history = vec![];
+
+            for _ in 0..200 {
+                if !history.is_empty() && rng.gen_ratio(1, 8) {
+                    let (chess_move, undo) = history.pop().unwrap();
+                    unsafe { board.unmake_move(chess_move, undo) };
+                } else {
+                    let Some(&chess_move) = mg::gen_moves(&board).choose(&mut rng) else {
+                        break;
+                    };
+
+                    history.push((chess_move, unsafe { board.make_move_unchecked(chess_move) }));
+                }
+
+                if let Err(violation) = board.validate_invariants() {
+                    panic!("{violation} on {board}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invariant_random_walk_tests() {
+        invariant_random_walk(500);
+    }
+
+    #[test]
+    #[ignore = "plays a million games"]
+    fn long_invariant_random_walk_tests() {
+        invariant_random_walk(1_000_000);
+    }
+
+    #[test]
+    fn invariant_violation_tests() {
+        let board =
+            Board::from_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        assert_eq!(board.validate_invariants(), Ok(()));
+
+        let broken = |corrupt: fn(&mut Board)| {
+            let mut corrupted = board;
+            corrupt(&mut corrupted);
+            corrupted
+                .validate_invariants()
+                .map_err(|InvariantViolation { broken }| broken)
+        };
+
+        assert_eq!(broken(|board| board.hash ^= 1), Err(vec![Invariant::Hash]));
+        assert_eq!(
+            broken(|board| board.checkers.toggle_bit(Square::E7)),
+            Err(vec![Invariant::Checkers])
+        );
+        assert_eq!(
+            broken(|board| board.pinned.toggle_bit(Square::D2)),
+            Err(vec![Invariant::Pinned])
+        );
+        assert_eq!(
+            broken(|board| board.them.occupation.toggle_bit(Square::D4)),
+            Err(vec![Invariant::Occupation])
+        );
+        assert_eq!(
+            broken(|board| board.piece_kind_board[Square::D4] = Some(PieceKind::Queen)),
+            Err(vec![Invariant::PieceKindBoard])
+        );
+        assert_eq!(
+            broken(|board| board.us.king.toggle_bit(Square::A3)),
+            Err(vec![
+                Invariant::KingCount,
+                Invariant::Occupation,
+                Invariant::PieceKindBoard,
+                Invariant::Hash
+            ])
+        );
+        assert_eq!(
+            broken(|board| {
+                board.us.queens.toggle_bit(Square::E7);
+                board.us.occupation.toggle_bit(Square::E7);
+            }),
+            Err(vec![Invariant::OverlappingPieces, Invariant::Hash])
+        );
+        assert_eq!(
+            broken(|board| board.us.castling_rights[Square::B1] = true),
+            Err(vec![Invariant::CastlingRights])
+        );
+        assert_eq!(
+            broken(|board| board.them.castling_rights[Square::A1] = true),
+            Err(vec![Invariant::CastlingRights])
+        );
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4, [197281, 1576, 0, 0, 0, 469, 0, 0, 8]; "starting position depth 4")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 4, [4085603, 757163, 1929, 128013, 15172, 25523, 42, 6, 43]; "kiwipete depth 4")]
+    #[test_case("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, [674624, 52051, 1165, 0, 0, 52950, 1292, 3, 0]; "position 3 depth 5")]
+    #[test_case("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 4, [422333, 131393, 0, 7795, 60032, 15492, 19, 0, 5]; "position 4 depth 4")]
+    fn detailed_perft_tests(fen_string: &str, depth: u32, expected_stats: [u64; 9]) {
+        let stats = Board::from_str(fen_string).unwrap().perft_detailed(depth);
+
+        assert_eq!(
+            [
+                stats.nodes,
+                stats.captures,
+                stats.en_passants,
+                stats.castles,
+                stats.promotions,
+                stats.checks,
+                stats.discovered_checks,
+                stats.double_checks,
+                stats.checkmates,
+            ],
+            expected_stats
+        );
+    }
+
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 4; "kiwipete depth 4")]
+    #[test_case("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 6; "position 3 depth 6")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 4; "chess960 depth 4")]
+    fn perft_variants_tests(fen_string: &str, depth: u32) {
+        let board = Board::from_str(fen_string).unwrap();
+        let nodes = board.perft(depth);
+
+        let divided = board.perft_divide(depth);
+        assert_eq!(divided.len(), mg::gen_moves(&board).len());
+        assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), nodes);
+
+        assert_eq!(board.perft_parallel(depth, 4, 1 << 16), nodes);
+        assert_eq!(board.perft_parallel(depth, 1, 1), nodes);
+    }
+
+    #[test_case(r#"1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - bm Qd1+; id "BK.01";"#, &["d6d1"], "BK.01"; "bratko-kopec")]
+    #[test_case(r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PP3PPP/R4RK1 w - - bm Qg6; id "WAC.001";"#, &["g3g6"], "WAC.001"; "win at chess")]
+    #[test_case(r#"1kr5/3n4/q3p2p/p2n2p1/PppB1P2/5BP1/1P2Q2P/3R2K1 w - - bm f5; id "Undermine.001"; c0 "f5=10, Be5+=2, Bf2=3, Bg4=2";"#, &["f4f5"], "Undermine.001"; "strategic test suite")]
+    #[test_case(r#"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - bm e1g1 e1c1; id "castles""#, &["e1g1", "e1c1"], "castles"; "long algebraic notation")]
+    fn epd_reading_tests(epd: &str, best_moves: &[&str], id: &str) {
+        let record = EpdRecord::from_str(epd).unwrap();
+
+        assert_eq!(
+            record.best_moves().unwrap(),
+            best_moves
+                .iter()
+                .map(|chess_move| ChessMove::from_str(chess_move).unwrap())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(record.id(), Some(id));
+        assert_eq!(EpdRecord::from_str(&record.to_string()).unwrap(), record);
+    }
+
+    #[test]
+    fn epd_operations() {
+        let epd = r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - am Qe2 Ke2; ce -15; pv Bb5 a6 Ba4; hmvc 2; fmvn 3; c9 "a \"quoted\" comment; with a semicolon"; dm 2; noop;"#;
+        let record = EpdRecord::from_str(epd).unwrap();
+
+        assert_eq!(record.board.min_ply_clock, 2);
+        assert_eq!(record.board.full_moves, 3);
+        assert_eq!(
+            record.operations,
+            [
+                EpdOperation::AvoidMoves(vec![
+                    ChessMove::from_str("d1e2").unwrap(),
+                    ChessMove::from_str("e1e2").unwrap()
+                ]),
+                EpdOperation::CentipawnEvaluation(-15),
+                EpdOperation::PrincipalVariation(vec![
+                    ChessMove::from_str("f1b5").unwrap(),
+                    ChessMove::from_str("a7a6").unwrap(),
+                    ChessMove::from_str("b5a4").unwrap()
+                ]),
+                EpdOperation::HalfMoveClock(2),
+                EpdOperation::FullMoveNumber(3),
+                EpdOperation::Comment(9, "a \"quoted\" comment; with a semicolon".to_string()),
+                EpdOperation::Other("dm".to_string(), vec!["2".to_string()]),
+                EpdOperation::Other("noop".to_string(), vec![]),
+            ]
+        );
+        assert_eq!(record.to_string(), epd);
+    }
+
+    #[test_case("8/8/8/8/8/8/8/8 w"; "missing fields")]
+    #[test_case("4k3/8/8/8/8/8/8/4K4 w - - bm e4;"; "invalid position")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - bm e4;"; "illegal move")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - id;"; "missing operand")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - ce 1.5;"; "invalid integer")]
+    #[test_case(r#"4k3/8/8/8/8/8/8/4K3 w - - id "unterminated;"#; "unterminated string")]
+    fn invalid_epd_tests(epd: &str) {
+        assert!(EpdRecord::from_str(epd).is_err());
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "starting position")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("8/7r/4Rn1p/1bP1P3/1P1kq3/2N1r3/p1p5/K3b3 w - - 0 1"; "random 1")]
+    #[test_case("1k5n/1Pp5/2pP4/4p1r1/5p2/1K1pBP2/1p1Q4/2N5 w - - 0 1"; "random 2")]
+    #[test_case("7b/3rr1P1/3P2pK/8/NN2Q2p/p1PB4/8/1b1k4 w - - 0 1"; "random 3")]
+    #[test_case("1Q6/QP6/n3n1p1/P5N1/1pp5/1p2K2p/6NN/1k6 w - - 0 1"; "random 4")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b KQkq c3 0 3"; "en passant test")]
+    #[test_case("r1bq1b1r/ppppk1pp/2n2n2/4pp2/2B1PP2/5N2/PPPP2PP/RNBQ1RK1 w - - 6 6"; "no castling test")]
+    #[test_case("1nbqkbnr/1ppppppp/r7/p7/7P/7R/PPPPPPP1/RNBQKBN1 w Qk - 2 3"; "partial castling test")]
+    #[test_case("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1"; "en passant move 1")]
+    #[test_case("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1"; "en passant move 2")]
+    #[test_case("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1"; "en passant move with check")]
+    #[test_case("5k2/8/8/8/8/8/8/4K2R w K - 0 1"; "king-side castle with check")]
+    #[test_case("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1"; "queen-side castle with check")]
+    #[test_case("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1"; "castle rights")]
+    #[test_case("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1"; "prevented castling")]
+    #[test_case("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1"; "promotion out of check")]
+    #[test_case("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1"; "discovered check")]
+    #[test_case("4k3/1P6/8/8/8/8/K7/8 w - - 0 1"; "promotion with check")]
+    #[test_case("8/P1k5/K7/8/8/8/8/8 w - - 0 1"; "under-promotion with check")]
+    #[test_case("K1k5/8/P7/8/8/8/8/8 w - - 0 1"; "self stalemate")]
+    #[test_case("8/k1P5/8/1K6/8/8/8/8 w - - 0 1"; "stalemate and checkmate 1")]
+    #[test_case("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1"; "stalemate and checkmate 2")]
+    #[test_case("r6r/1b2k1bq/8/8/7B/8/8/R3K2R b KQ - 3 2"; "misc 1")]
+    #[test_case("8/8/8/2k5/2pP4/8/B7/4K3 b - d3 0 3"; "misc 2")]
+    #[test_case("r1bqkbnr/pppppppp/n7/8/8/P7/1PPPPPPP/RNBQKBNR w KQkq - 2 2"; "misc 3")]
+    #[test_case("r3k2r/p1pp1pb1/bn2Qnp1/2qPN3/1p2P3/2N5/PPPBBPPP/R3K2R b KQkq - 3 2"; "misc 4")]
+    #[test_case("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"; "misc 5")]
+    #[test_case("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"; "misc 6")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"; "chess960 1")]
+    #[test_case("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9"; "chess960 2")]
+    #[test_case("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9"; "chess960 3")]
+    #[test_case("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9"; "chess960 4")]
+    #[test_case("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9"; "chess960 5")]
+    #[test_case("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9"; "chess960 6")]
+    #[test_case("q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9"; "chess960 7")]
+    #[test_case("qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9"; "chess960 8")]
+    #[test_case("qnnbbrkr/1p2ppp1/2pp3p/p7/1P5P/2NP4/P1P1PPP1/Q1NBBRKR w HFhf - 0 9"; "chess960 9")]
+    #[test_case("qn1rbbkr/ppp2p1p/1n1pp1p1/8/3P4/P6P/1PP1PPPK/QNNRBB1R w hd - 2 9"; "chess960 10")]
+    #[test_case("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1"; "shredder-fen with inner rooks")]
+    #[test_case("rk2r2r/8/8/8/8/8/8/RK2R2R w Ea - 0 1"; "shredder-fen with an ambiguous rook")]
+    #[test_case("rk2r2r/8/8/8/8/8/8/RK2R2R w Eq - 0 1"; "x-fen with an ambiguous rook")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"; "shredder-fen of the starting position")]
+    fn packed_board_tests(fen_string: &str) {
+        fn check_packing(board: &Board, depth: u32) {
+            let unpacked_board = Board::from_packed(&board.to_packed()).unwrap();
+
+            // Castling rights left on irrelevant squares aren't kept, so the boards are compared
+            // through their FEN strings
+            assert_eq!(unpacked_board.to_string(), board.to_string());
+            assert_eq!(unpacked_board.hash, board.hash);
+            assert_eq!(unpacked_board.checkers, board.checkers);
+            assert_eq!(unpacked_board.pinned, board.pinned);
+
+            if depth > 0 {
+                for (_, child_board) in board.gen_child_boards() {
+                    check_packing(&child_board, depth - 1);
+                }
+            }
+        }
+
+        check_packing(&Board::from_str(fen_string).unwrap(), 2);
+    }
+
+    #[test]
+    fn invalid_packed_boards() {
+        let packed = Board::starting_position().to_packed();
+
+        // Replacing the bishop on f1 by a second castling rook on the king side
+        let mut two_castling_rooks = packed;
+        two_castling_rooks.0[8 + 2] = 12 << 4 | 5;
+        assert!(matches!(
+            Board::from_packed(&two_castling_rooks),
+            Err(UnpackBoardError::InvalidCastlingRights)
+        ));
+
+        let mut invalid_code = packed;
+        invalid_code.0[8] |= 0xf;
+        assert!(matches!(
+            Board::from_packed(&invalid_code),
+            Err(UnpackBoardError::InvalidPieceCode)
+        ));
+
+        let mut too_many_pieces = packed;
+        too_many_pieces.0[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            Board::from_packed(&too_many_pieces),
+            Err(UnpackBoardError::TooManyPieces)
+        ));
+
+        // Replacing the white king by a queen, in a position without castling rights
+        let mut missing_king = Board::from_str("8/8/8/4k3/8/8/8/4K3 w - - 0 1")
+            .unwrap()
+            .to_packed();
+        missing_king.0[8] = 11 << 4 | 4;
+        assert!(matches!(
+            Board::from_packed(&missing_king),
+            Err(UnpackBoardError::InvalidBoard(_))
+        ));
+    }
+
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"; "promotions")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"; "chess960")]
+    fn packed_move_tests(fen_string: &str) {
+        fn check_packing(board: &Board, depth: u32) {
+            let moves = mg::gen_moves(board);
+            let packed_moves = moves
+                .iter()
+                .map(|&chess_move| PackedMove::from(chess_move))
+                .collect::<Vec<_>>();
+
+            for (index, (&chess_move, &packed_move)) in moves.iter().zip(&packed_moves).enumerate()
+            {
+                assert!(!packed_move.is_null());
+                assert_eq!(packed_move.unpack(), Some(chess_move));
+                assert!(!packed_moves[index + 1..].contains(&packed_move));
+            }
+
+            if depth > 0 {
+                for (_, child_board) in board.gen_child_boards() {
+                    check_packing(&child_board, depth - 1);
+                }
+            }
+        }
+
+        check_packing(&Board::from_str(fen_string).unwrap(), 2);
+        assert_eq!(PackedMove::NULL.unpack(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "starting position")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b KQkq c3 0 3"; "en passant test")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"; "chess960")]
+    fn serde_board_tests(fen_string: &str) {
+        let board = Board::from_str(fen_string).unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+
+        let fen = match board.castling_mode {
+            CastlingMode::Standard => board.to_string(),
+            CastlingMode::Chess960 => board.to_shredder_fen(),
+        };
+
+        assert_eq!(json, format!("\"{fen}\""));
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+
+        let bytes = bincode::serialize(&board).unwrap();
+
+        assert_eq!(bytes.len(), crate::packed::PACKED_BOARD_SIZE);
+        assert_eq!(
+            bincode::deserialize::<Board>(&bytes).unwrap().to_string(),
+            board.to_string()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_chess960_board_with_corner_rooks() {
+        let mut board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        board.castling_mode = CastlingMode::Chess960;
+
+        let mut deserialized =
+            serde_json::from_str::<Board>(&serde_json::to_string(&board).unwrap()).unwrap();
+
+        assert_eq!(deserialized.castling_mode, CastlingMode::Chess960);
+        assert!(deserialized
+            .make_move(ChessMove::from_str("e1h1").unwrap())
+            .is_ok());
+        assert_eq!(
+            serde_json::from_str::<Board>(r#""r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1""#)
+                .unwrap()
+                .castling_mode,
+            CastlingMode::Standard
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_values() {
+        let chess_move = ChessMove::from_str("e7e8n").unwrap();
+
+        assert_eq!(serde_json::to_string(&chess_move).unwrap(), "\"e7e8n\"");
+        assert_eq!(
+            serde_json::from_str::<ChessMove>("\"e7e8n\"").unwrap(),
+            chess_move
+        );
+        assert_eq!(bincode::serialize(&chess_move).unwrap().len(), 2);
+        assert_eq!(
+            bincode::deserialize::<ChessMove>(&bincode::serialize(&chess_move).unwrap()).unwrap(),
+            chess_move
+        );
+        assert!(bincode::deserialize::<ChessMove>(&PackedMove::NULL.0.to_le_bytes()).is_err());
+
+        assert_eq!(serde_json::to_string(&Square::E4).unwrap(), "\"e4\"");
+        assert_eq!(bincode::serialize(&Square::E4).unwrap(), [28]);
+        assert!(serde_json::from_str::<Square>("\"i9\"").is_err());
+        assert_eq!(serde_json::to_string(&Color::Black).unwrap(), "\"black\"");
+        assert_eq!(
+            serde_json::to_string(&PieceKind::Knight).unwrap(),
+            "\"knight\""
+        );
+        assert_eq!(serde_json::to_string(&BitBoard(5)).unwrap(), "5");
+        assert_eq!(
+            serde_json::to_string(&Outcome::Win(Color::White)).unwrap(),
+            r#"{"win":"white"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Outcome>(r#"{"draw":"threefold_repetition"}"#).unwrap(),
+            Outcome::Draw(DrawReason::ThreefoldRepetition)
+        );
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4; "starting position")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3; "kiwipete")]
+    #[test_case("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 4; "en passant move with check")]
+    #[test_case("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 3; "prevented castling")]
+    #[test_case("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 4; "promotion out of check")]
+    #[test_case("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 4; "discovered check")]
+    #[test_case("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 3; "misc 5")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 3; "chess960 1")]
+    #[test_case("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", 3; "chess960 4")]
+    fn flipped_perft_tests(fen_string: &str, depth: u32) {
+        let board = Board::from_str(fen_string).unwrap();
+        let flipped_board = board.flipped();
+
+        assert_ne!(flipped_board.playing_color, board.playing_color);
+        assert_eq!(flipped_board.flipped().to_string(), board.to_string());
+        assert_eq!(flipped_board.flipped().hash, board.hash);
+        assert_eq!(flipped_board.perft(depth), board.perft(depth));
+
+        let mut moves = mg::gen_moves(&board)
+            .into_iter()
+            .map(|chess_move| chess_move.flipped().to_string())
+            .collect::<Vec<_>>();
+        let mut flipped_moves = mg::gen_moves(&flipped_board)
+            .into_iter()
+            .map(|chess_move| chess_move.to_string())
+            .collect::<Vec<_>>();
+
+        moves.sort();
+        flipped_moves.sort();
+        assert_eq!(moves, flipped_moves);
+
+        match board.mirrored_horizontally() {
+            Some(mirrored_board) => {
+                assert_eq!(
+                    mirrored_board.mirrored_horizontally().unwrap().to_string(),
+                    board.to_string()
+                );
+                assert_eq!(mirrored_board.perft(depth), board.perft(depth));
+                assert!(mg::gen_moves(&board)
+                    .into_iter()
+                    .all(|chess_move| mirrored_board.is_legal(chess_move.mirrored_horizontally())));
+            }
+            None => assert!(
+                board.us.can_castle_king_side()
+                    || board.us.can_castle_queen_side()
+                    || board.them.can_castle_king_side()
+                    || board.them.can_castle_queen_side()
+            ),
+        }
+    }
+
+    #[test]
+    fn flipped_board() {
+        let board =
+            Board::from_str("rnbqkbnr/ppp1pppp/8/8/1PPpP3/8/P2P1PPP/RNBQKBNR b Kq c3 0 3").unwrap();
+
+        assert_eq!(
+            board.flipped().to_string(),
+            "rnbqkbnr/p2p1ppp/8/1ppPp3/8/8/PPP1PPPP/RNBQKBNR w Qk c6 0 3"
+        );
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b96181691fc9c; "starting position")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", 0x823c9b50fd114196; "e4")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", 0x0756b94461c50fb0; "e4 d5")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4; "e4 d5 e5")]
+    #[test_case("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78; "e4 d5 e5 f5")]
+    #[test_case("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 1 3", 0x652a607ca3f242c1; "e4 d5 e5 f5 ke2")]
+    #[test_case("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 2 4", 0x00fdd303c946bdd9; "e4 d5 e5 f5 ke2 kf7")]
+    #[test_case("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637; "a4 b5 h4 b4 c4")]
+    #[test_case("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 1 4", 0x5c3f9b829b279560; "a4 b5 h4 b4 c4 bxc3 ra3")]
+    fn polyglot_key_tests(fen_string: &str, key: u64) {
+        assert_eq!(Board::from_str(fen_string).unwrap().polyglot_key(), key);
+    }
+
+    // Hashes must stay the same between builds, as they are persisted. Changing them requires
+    // bumping `ZOBRIST_VERSION`.
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x0344cb204c62661c; "starting position")]
+    #[test_case("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0xe48231d8bf7359b6; "en passant")]
+    #[test_case("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 2 4", 0xf6ec1bf46cda2dbd; "no castling rights")]
+    fn zobrist_stability_tests(fen_string: &str, hash: u64) {
+        if ZOBRIST_SEED != 0x73130172E6DEA605 {
+            return;
+        }
+
+        assert_eq!(ZOBRIST_VERSION, 1);
+        assert_eq!(Board::from_str(fen_string).unwrap().hash, hash);
+    }
+
+    fn polyglot_entry(key: u64, origin: Square, target: Square, weight: u16) -> [u8; 16] {
+        let raw_move = (origin.as_index() << 6 | target.as_index()) as u16;
+        let mut entry = [0; 16];
+
+        entry[0..8].copy_from_slice(&key.to_be_bytes());
+        entry[8..10].copy_from_slice(&raw_move.to_be_bytes());
+        entry[10..12].copy_from_slice(&weight.to_be_bytes());
+        entry
+    }
+
+    #[test]
+    fn polyglot_book() {
+        let starting_board = Board::starting_position();
+        let castling_board =
+            Board::from_str("r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+                .unwrap();
+
+        let mut entries = [
+            polyglot_entry(starting_board.polyglot_key(), Square::E2, Square::E4, 3),
+            polyglot_entry(starting_board.polyglot_key(), Square::D2, Square::D4, 1),
+            // An illegal move, which must be skipped
+            polyglot_entry(starting_board.polyglot_key(), Square::E2, Square::E5, 100),
+            polyglot_entry(castling_board.polyglot_key(), Square::E1, Square::H1, 1),
+        ];
+        entries.sort_by_key(|entry| u64::from_be_bytes(entry[0..8].try_into().unwrap()));
+
+        let book = PolyglotBook::read(&entries.concat()[..]).unwrap();
+
+        assert_eq!(book.entries(&starting_board).len(), 3);
+        assert_eq!(
+            book.best_move(&starting_board),
+            Some(ChessMove::from_str("e2e4").unwrap())
+        );
+        assert_eq!(
+            book.best_move(&castling_board),
+            Some(ChessMove::from_str("e1g1").unwrap())
+        );
+
+        let mut chess960_castling_board = castling_board;
+        chess960_castling_board.castling_mode = CastlingMode::Chess960;
+
+        assert_eq!(
+            book.best_move(&chess960_castling_board),
+            Some(ChessMove::from_str("e1h1").unwrap())
+        );
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut e4_count = 0;
+
+        for _ in 0..1000 {
+            let chess_move = book
+                .weighted_random_move(&starting_board, &mut rng)
+                .unwrap();
+
+            assert!(["e2e4", "d2d4"].contains(&&chess_move.to_string()[..]));
+            e4_count += (chess_move.to_string() == "e2e4") as u32;
+        }
+
+        assert!((650..850).contains(&e4_count));
+        assert!(book
+            .weighted_random_move(
+                &Board::from_str("8/8/8/4k3/8/8/8/4K3 w - - 0 1").unwrap(),
+                &mut rng
+            )
+            .is_none());
+
+        entries.reverse();
+        assert!(matches!(
+            PolyglotBook::read(&entries.concat()[..]),
+            Err(ReadPolyglotBookError::UnsortedEntries)
+        ));
+        assert!(matches!(
+            PolyglotBook::from_bytes(&[0; 17]),
+            Err(ReadPolyglotBookError::InvalidLength)
+        ));
+    }
 }
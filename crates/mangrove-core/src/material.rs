@@ -0,0 +1,146 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use mangrove_bootstrap::Color;
+
+use crate::{board::Board, repr::PieceKind};
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum ParseMaterialSignatureError {
+    #[error("material signature should have two sides separated by a `v`")]
+    MissingSeparator,
+    #[error("each side should start with a single king")]
+    InvalidKings,
+    #[error("pieces must be a `Q`, `R`, `B`, `N` or `P`")]
+    InvalidPiece,
+}
+
+/// The pieces of each side other than their king, written as in `KQPvKR`, with white first.
+/// Each side is sorted from the queens to the pawns, so a material has a single signature.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MaterialSignature {
+    pub white: Vec<PieceKind>,
+    pub black: Vec<PieceKind>,
+}
+
+// The position of a kind in `PieceKind::ALL`, which is the order pieces are sorted in.
+fn kind_order(kind: PieceKind) -> usize {
+    PieceKind::ALL
+        .iter()
+        .position(|&other| other == kind)
+        .unwrap()
+}
+
+impl MaterialSignature {
+    /// Returns the material on the board.
+    pub fn of_board(board: &Board) -> Self {
+        let side = |color| {
+            PieceKind::ALL
+                .into_iter()
+                .skip(1)
+                .flat_map(|kind| {
+                    let count = board.player(color).piece_bitboard(kind).count_ones();
+                    (0..count).map(move |_| kind)
+                })
+                .collect()
+        };
+
+        Self {
+            white: side(Color::White),
+            black: side(Color::Black),
+        }
+    }
+
+    pub fn side(&self, color: Color) -> &[PieceKind] {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    /// Sorts the pieces of each side, after they were changed in place.
+    pub fn sort(&mut self) {
+        self.white.sort_by_key(|&kind| kind_order(kind));
+        self.black.sort_by_key(|&kind| kind_order(kind));
+    }
+
+    /// Returns the material with the sides swapped.
+    pub fn flipped(&self) -> Self {
+        Self {
+            white: self.black.clone(),
+            black: self.white.clone(),
+        }
+    }
+
+    /// Returns the number of pieces other than the kings.
+    pub fn piece_count(&self) -> usize {
+        self.white.len() + self.black.len()
+    }
+
+    /// Returns the pieces other than the kings, white ones first.
+    pub fn pieces(&self) -> impl Iterator<Item = (Color, PieceKind)> + '_ {
+        let white = self.white.iter().map(|&kind| (Color::White, kind));
+        let black = self.black.iter().map(|&kind| (Color::Black, kind));
+
+        white.chain(black)
+    }
+
+    /// Checks if either side has a pawn.
+    pub fn has_pawns(&self) -> bool {
+        self.pieces().any(|(_, kind)| kind == PieceKind::Pawn)
+    }
+}
+
+impl FromStr for MaterialSignature {
+    type Err = ParseMaterialSignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (white, black) = s
+            .split_once('v')
+            .ok_or(ParseMaterialSignatureError::MissingSeparator)?;
+
+        let parse_side = |side: &str| -> Result<Vec<_>, _> {
+            let pieces = side
+                .strip_prefix('K')
+                .ok_or(ParseMaterialSignatureError::InvalidKings)?;
+
+            pieces
+                .chars()
+                .map(|character| {
+                    match PieceKind::from_str(&character.to_ascii_lowercase().to_string()) {
+                        Ok(PieceKind::King) => Err(ParseMaterialSignatureError::InvalidKings),
+                        Ok(kind) if character.is_ascii_uppercase() => Ok(kind),
+                        _ => Err(ParseMaterialSignatureError::InvalidPiece),
+                    }
+                })
+                .collect()
+        };
+
+        let mut material = Self {
+            white: parse_side(white)?,
+            black: parse_side(black)?,
+        };
+        material.sort();
+
+        Ok(material)
+    }
+}
+
+impl Display for MaterialSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (color, separator) in [(Color::White, "v"), (Color::Black, "")] {
+            write!(f, "K")?;
+
+            for kind in self.side(color) {
+                write!(f, "{}", kind.to_string().to_ascii_uppercase())?;
+            }
+
+            write!(f, "{separator}")?;
+        }
+
+        Ok(())
+    }
+}
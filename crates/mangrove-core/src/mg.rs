@@ -4,7 +4,7 @@ use mangrove_bootstrap::{BitBoard, Color, Square};
 use crate::{
     board::Board,
     index,
-    repr::{ChessMove, PieceKind},
+    repr::{CastlingSide, ChessMove, PieceKind},
 };
 
 /// The maximum number of moves stored by [`Moves`]. This shouldn't be relevant for most
@@ -30,6 +30,45 @@ impl CheckType for NotInCheck {
     const IN_CHECK: bool = false;
 }
 
+trait GenType {
+    /// Whether captures and promotions, including en passant and non-capturing promotions, are
+    /// generated.
+    const CAPTURES: bool;
+    /// Whether quiet moves, including castles, are generated.
+    const QUIETS: bool;
+
+    /// Returns the targets allowed for moves other than promotions, en passant and castles.
+    fn targets(board: &Board) -> BitBoard {
+        match (Self::CAPTURES, Self::QUIETS) {
+            (true, true) => BitBoard::FULL,
+            (true, false) => board.them.occupation,
+            (false, true) => !board.them.occupation,
+            (false, false) => BitBoard::EMPTY,
+        }
+    }
+}
+
+struct AllMoves;
+
+struct Captures;
+
+struct Quiets;
+
+impl GenType for AllMoves {
+    const CAPTURES: bool = true;
+    const QUIETS: bool = true;
+}
+
+impl GenType for Captures {
+    const CAPTURES: bool = true;
+    const QUIETS: bool = false;
+}
+
+impl GenType for Quiets {
+    const CAPTURES: bool = false;
+    const QUIETS: bool = true;
+}
+
 trait Gen {
     const PIECE_KIND: PieceKind;
 
@@ -40,7 +79,7 @@ trait Gen {
         color: Color,
     ) -> BitBoard;
 
-    fn legal_moves<C: CheckType>(board: &Board, moves: &mut Moves) {
+    fn legal_moves<C: CheckType, G: GenType>(board: &Board, moves: &mut impl Extend<ChessMove>) {
         let pieces = board.us.piece_bitboard(Self::PIECE_KIND);
         let occupation = board.occupation();
 
@@ -62,7 +101,7 @@ trait Gen {
                 )
         } else {
             BitBoard::FULL
-        };
+        } & G::targets(board);
 
         moves.extend((pieces & !board.pinned).bits().flat_map(|piece| {
             (Self::pseudo_legal_moves(piece, board.us.occupation, occupation, board.playing_color)
@@ -82,7 +121,8 @@ trait Gen {
                     board.us.occupation,
                     occupation,
                     board.playing_color,
-                ) & index::line_fit(king_square, piece))
+                ) & index::line_fit(king_square, piece)
+                    & G::targets(board))
                 .bits()
                 .map(move |target| ChessMove {
                     origin: piece,
@@ -97,7 +137,7 @@ trait Gen {
 pub struct Pawn;
 
 impl Pawn {
-    unsafe fn is_legal_en_passant_capture(
+    pub(crate) unsafe fn is_legal_en_passant_capture(
         board: &Board,
         en_passant_capture_square: Square,
         origin: Square,
@@ -141,7 +181,7 @@ impl Gen for Pawn {
         )
     }
 
-    fn legal_moves<C: CheckType>(board: &Board, moves: &mut Moves) {
+    fn legal_moves<C: CheckType, G: GenType>(board: &Board, moves: &mut impl Extend<ChessMove>) {
         let occupation = board.occupation();
 
         // SAFETY: The board is assumed to be validly constructed
@@ -164,6 +204,9 @@ impl Gen for Pawn {
             BitBoard::FULL
         };
 
+        // Promotions are generated along with captures, regardless of their target
+        let targets = G::targets(board) & !BitBoard::EDGE_RANKS;
+
         moves.extend((board.us.pawns & !board.pinned).bits().flat_map(|piece| {
             ((Self::pseudo_legal_moves(
                 piece,
@@ -171,7 +214,7 @@ impl Gen for Pawn {
                 occupation,
                 board.playing_color,
             ) & valid_targets)
-                & !BitBoard::EDGE_RANKS)
+                & targets)
                 .bits()
                 .map(move |target| ChessMove {
                     origin: piece,
@@ -181,21 +224,27 @@ impl Gen for Pawn {
         }));
 
         // Promotions
-        moves.extend((board.us.pawns & !board.pinned).bits().flat_map(|piece| {
-            (Self::pseudo_legal_moves(piece, board.us.occupation, occupation, board.playing_color)
-                & valid_targets
-                & BitBoard::EDGE_RANKS)
-                .bits()
-                .flat_map(move |target| {
-                    PieceKind::PROMOTIONS
-                        .into_iter()
-                        .map(move |kind| ChessMove {
-                            origin: piece,
-                            target,
-                            promotion: Some(kind),
-                        })
-                })
-        }));
+        if G::CAPTURES {
+            moves.extend((board.us.pawns & !board.pinned).bits().flat_map(|piece| {
+                (Self::pseudo_legal_moves(
+                    piece,
+                    board.us.occupation,
+                    occupation,
+                    board.playing_color,
+                ) & valid_targets
+                    & BitBoard::EDGE_RANKS)
+                    .bits()
+                    .flat_map(move |target| {
+                        PieceKind::PROMOTIONS
+                            .into_iter()
+                            .map(move |kind| ChessMove {
+                                origin: piece,
+                                target,
+                                promotion: Some(kind),
+                            })
+                    })
+            }));
+        }
 
         if !C::IN_CHECK {
             moves.extend((board.us.pawns & board.pinned).bits().flat_map(|piece| {
@@ -205,7 +254,7 @@ impl Gen for Pawn {
                     occupation,
                     board.playing_color,
                 ) & index::line_fit(king_square, piece))
-                    & !BitBoard::EDGE_RANKS)
+                    & targets)
                     .bits()
                     .map(move |target| ChessMove {
                         origin: piece,
@@ -215,28 +264,34 @@ impl Gen for Pawn {
             }));
 
             // Promotions
-            moves.extend((board.us.pawns & board.pinned).bits().flat_map(|piece| {
-                (Self::pseudo_legal_moves(
-                    piece,
-                    board.us.occupation,
-                    occupation,
-                    board.playing_color,
-                ) & index::line_fit(king_square, piece)
-                    & BitBoard::EDGE_RANKS)
-                    .bits()
-                    .flat_map(move |target| {
-                        PieceKind::PROMOTIONS
-                            .into_iter()
-                            .map(move |kind| ChessMove {
-                                origin: piece,
-                                target,
-                                promotion: Some(kind),
-                            })
-                    })
-            }));
+            if G::CAPTURES {
+                moves.extend((board.us.pawns & board.pinned).bits().flat_map(|piece| {
+                    (Self::pseudo_legal_moves(
+                        piece,
+                        board.us.occupation,
+                        occupation,
+                        board.playing_color,
+                    ) & index::line_fit(king_square, piece)
+                        & BitBoard::EDGE_RANKS)
+                        .bits()
+                        .flat_map(move |target| {
+                            PieceKind::PROMOTIONS
+                                .into_iter()
+                                .map(move |kind| ChessMove {
+                                    origin: piece,
+                                    target,
+                                    promotion: Some(kind),
+                                })
+                        })
+                }));
+            }
         }
 
         // En passants
+        if !G::CAPTURES {
+            return;
+        }
+
         unsafe {
             if let Some(en_passant_capture_square) = board.en_passant_capture_square {
                 for origin in
@@ -249,11 +304,11 @@ impl Gen for Pawn {
                             origin,
                         )
                     {
-                        moves.push(ChessMove {
+                        moves.extend([ChessMove {
                             origin,
                             target: en_passant_capture_square,
                             promotion: None,
-                        });
+                        }]);
                     }
                 }
             }
@@ -278,7 +333,7 @@ impl Gen for Knight {
     // This is essentially identical to the regular `legal_moves`, except we don't care about pinned
     // pieces, as a pinned knight cannot move.
 
-    fn legal_moves<C: CheckType>(board: &Board, moves: &mut Moves) {
+    fn legal_moves<C: CheckType, G: GenType>(board: &Board, moves: &mut impl Extend<ChessMove>) {
         let occupation = board.occupation();
 
         // SAFETY: The board is assumed to be validly constructed
@@ -298,7 +353,7 @@ impl Gen for Knight {
                 )
         } else {
             BitBoard::FULL
-        };
+        } & G::targets(board);
 
         moves.extend((board.us.knights & !board.pinned).bits().flat_map(|piece| {
             (Self::pseudo_legal_moves(piece, board.us.occupation, occupation, board.playing_color)
@@ -373,16 +428,16 @@ impl Gen for King {
         index::king_attacks(origin) & !friendly_occupation
     }
 
-    fn legal_moves<C: CheckType>(board: &Board, moves: &mut Moves) {
+    fn legal_moves<C: CheckType, G: GenType>(board: &Board, moves: &mut impl Extend<ChessMove>) {
         let king_square = Square::try_from(board.us.king).unwrap();
 
         moves.extend(
-            Self::pseudo_legal_moves(
+            (Self::pseudo_legal_moves(
                 king_square,
                 board.us.occupation,
                 board.occupation(),
                 board.playing_color,
-            )
+            ) & G::targets(board))
             .bits()
             .filter(|square| !board.is_attacked_by_them(*square))
             .map(|target| ChessMove {
@@ -393,67 +448,157 @@ impl Gen for King {
         );
 
         // Castles
-        if !C::IN_CHECK {
-            let king_side_castle_mask = BitBoard::king_side_castle_mask(board.playing_color);
-
-            if board.us.castling_rights.can_castle_king_side()
-                && (king_side_castle_mask & board.occupation()).is_empty()
-                && (king_side_castle_mask
-                    .bits()
-                    .all(|square| !board.is_attacked_by_them(square)))
-            {
-                moves.push(ChessMove {
-                    origin: king_square,
-                    target: match board.playing_color {
-                        Color::White => Square::G1,
-                        Color::Black => Square::G8,
-                    },
-                    promotion: None,
-                });
-            }
-
-            if board.us.castling_rights.can_castle_queen_side()
-                && (BitBoard::queen_side_castle_occupation_mask(board.playing_color)
-                    & board.occupation())
-                .is_empty()
-                && (BitBoard::queen_side_castle_attack_mask(board.playing_color)
-                    .bits()
-                    .all(|square| !board.is_attacked_by_them(square)))
-            {
-                moves.push(ChessMove {
-                    origin: king_square,
-                    target: match board.playing_color {
-                        Color::White => Square::C1,
-                        Color::Black => Square::C8,
-                    },
-                    promotion: None,
-                });
+        if !C::IN_CHECK && G::QUIETS {
+            for side in CastlingSide::ALL {
+                let Some(rook_square) = board.us.castling_rook(side) else {
+                    continue;
+                };
+
+                if King::is_legal_castle(board, side, rook_square) {
+                    moves.extend([board.castling_move(side, rook_square)]);
+                }
             }
         }
     }
 }
 
-pub fn gen_moves(board: &Board) -> Moves {
-    let mut moves = Moves::new();
+impl King {
+    /// Checks whether castling on the passed side with the rook on the passed square is legal,
+    /// given that the king isn't in check and has the right to castle with that rook.
+    pub(crate) fn is_legal_castle(board: &Board, side: CastlingSide, rook_square: Square) -> bool {
+        // SAFETY: The board is assumed to be validly constructed
+        let king_square = unsafe { Square::try_from(board.us.king).unwrap_unchecked() };
+
+        let king_target = side.king_target(board.playing_color);
+        let rook_target = side.rook_target(board.playing_color);
+
+        let king_path = index::line_between(king_square, king_target) | BitBoard::from(king_target);
+        let rook_path = index::line_between(rook_square, rook_target) | BitBoard::from(rook_target);
 
+        // The castling king and rook don't block each other, and in Chess960 the rook may shield
+        // the king's path from an attack along the back rank
+        let occupation =
+            board.occupation() & !BitBoard::from(king_square) & !BitBoard::from(rook_square);
+
+        ((king_path | rook_path) & occupation).is_empty()
+            && king_path
+                .bits()
+                .all(|square| !board.is_attacked_by_them_with_occupation(square, occupation))
+    }
+}
+
+fn gen<G: GenType>(board: &Board, moves: &mut impl Extend<ChessMove>) {
     if board.in_check() {
-        King::legal_moves::<InCheck>(board, &mut moves);
+        King::legal_moves::<InCheck, G>(board, moves);
 
         if board.checkers.count_ones() < 2 {
-            Pawn::legal_moves::<InCheck>(board, &mut moves);
-            Knight::legal_moves::<InCheck>(board, &mut moves);
-            Bishop::legal_moves::<InCheck>(board, &mut moves);
-            Rook::legal_moves::<InCheck>(board, &mut moves);
-            Queen::legal_moves::<InCheck>(board, &mut moves);
+            Pawn::legal_moves::<InCheck, G>(board, moves);
+            Knight::legal_moves::<InCheck, G>(board, moves);
+            Bishop::legal_moves::<InCheck, G>(board, moves);
+            Rook::legal_moves::<InCheck, G>(board, moves);
+            Queen::legal_moves::<InCheck, G>(board, moves);
         }
     } else {
-        King::legal_moves::<NotInCheck>(board, &mut moves);
-        Pawn::legal_moves::<NotInCheck>(board, &mut moves);
-        Knight::legal_moves::<NotInCheck>(board, &mut moves);
-        Bishop::legal_moves::<NotInCheck>(board, &mut moves);
-        Rook::legal_moves::<NotInCheck>(board, &mut moves);
-        Queen::legal_moves::<NotInCheck>(board, &mut moves);
+        King::legal_moves::<NotInCheck, G>(board, moves);
+        Pawn::legal_moves::<NotInCheck, G>(board, moves);
+        Knight::legal_moves::<NotInCheck, G>(board, moves);
+        Bishop::legal_moves::<NotInCheck, G>(board, moves);
+        Rook::legal_moves::<NotInCheck, G>(board, moves);
+        Queen::legal_moves::<NotInCheck, G>(board, moves);
     }
+}
+
+pub fn gen_moves(board: &Board) -> Moves {
+    let mut moves = Moves::new();
+
+    gen::<AllMoves>(board, &mut moves);
 
     moves
 }
+
+/// Generates the legal captures and promotions of the board, including en passant captures and
+/// promotions which don't capture, into the passed sink.
+pub fn gen_captures(board: &Board, moves: &mut impl Extend<ChessMove>) {
+    gen::<Captures>(board, moves);
+}
+
+/// Generates the legal quiet moves of the board, which are all moves that neither capture nor
+/// promote, including castles, into the passed sink.
+///
+/// Together with [`gen_captures`] this generates every legal move exactly once.
+pub fn gen_quiets(board: &Board, moves: &mut impl Extend<ChessMove>) {
+    gen::<Quiets>(board, moves);
+}
+
+/// Generates the legal quiet moves of the board which give check, into the passed sink. See
+/// [`gen_quiets`].
+pub fn gen_quiet_checks(board: &Board, moves: &mut impl Extend<ChessMove>) {
+    gen::<Quiets>(
+        board,
+        &mut CheckFilter {
+            board,
+            inner: moves,
+        },
+    );
+}
+
+// A sink which only passes on the moves giving check.
+struct CheckFilter<'a, E> {
+    board: &'a Board,
+    inner: &'a mut E,
+}
+
+impl<E: Extend<ChessMove>> Extend<ChessMove> for CheckFilter<'_, E> {
+    fn extend<T: IntoIterator<Item = ChessMove>>(&mut self, iter: T) {
+        let board = self.board;
+
+        self.inner.extend(
+            iter.into_iter()
+                .filter(|&chess_move| gives_check(board, chess_move)),
+        );
+    }
+}
+
+/// Returns whether the passed move, assumed to be legal, puts the opponent in check.
+pub(crate) fn gives_check(board: &Board, chess_move: ChessMove) -> bool {
+    let mut us = board.us;
+    let mut occupation = board.occupation();
+
+    if let Some(side) = board.castling_side(chess_move) {
+        // SAFETY: Castles are only recognized when the castling rook is present
+        let rook_square = unsafe { board.us.castling_rook(side).unwrap_unchecked() };
+        let king_target = side.king_target(board.playing_color);
+        let rook_target = side.rook_target(board.playing_color);
+
+        us.toggle_piece(chess_move.origin, PieceKind::King);
+        us.toggle_piece(rook_square, PieceKind::Rook);
+        us.toggle_piece(king_target, PieceKind::King);
+        us.toggle_piece(rook_target, PieceKind::Rook);
+
+        occupation = occupation & !BitBoard::from(chess_move.origin) & !BitBoard::from(rook_square);
+        occupation |= BitBoard::from(king_target) | BitBoard::from(rook_target);
+    } else {
+        // SAFETY: The move is assumed to be legal, so there is a piece on its origin
+        let kind = unsafe { board.piece_kind_board[chess_move.origin].unwrap_unchecked() };
+
+        us.toggle_piece(chess_move.origin, kind);
+        us.toggle_piece(chess_move.target, chess_move.promotion.unwrap_or(kind));
+
+        occupation = occupation & !BitBoard::from(chess_move.origin);
+        occupation |= BitBoard::from(chess_move.target);
+
+        if kind == PieceKind::Pawn && Some(chess_move.target) == board.en_passant_capture_square {
+            occupation =
+                occupation & !BitBoard::from(chess_move.target).move_one_down(board.playing_color);
+        }
+    }
+
+    // SAFETY: The board is assumed to be validly constructed
+    let king_square = unsafe { Square::try_from(board.them.king).unwrap_unchecked() };
+
+    !((index::rook_slides(king_square, occupation) & (us.rooks | us.queens))
+        | (index::bishop_slides(king_square, occupation) & (us.bishops | us.queens))
+        | (index::knight_attacks(king_square) & us.knights)
+        | (index::pawn_attacks(king_square, !board.playing_color) & us.pawns))
+        .is_empty()
+}
@@ -0,0 +1,269 @@
+use mangrove_bootstrap::{BitBoard, Color, Square};
+
+use crate::{
+    board::{Board, ParseBoardError},
+    repr::{back_rank, CastlingMode, CastlingSide, ChessMove, PieceKind, Player},
+};
+
+// The piece codes stored in the nibbles of a packed board. Codes up to `BLACK` + 5 are the
+// pieces of each color, offset by the index of their kind in `PieceKind::ALL`.
+const WHITE: u8 = 0;
+const BLACK: u8 = 6;
+const WHITE_CASTLING_ROOK: u8 = 12;
+const BLACK_CASTLING_ROOK: u8 = 13;
+// A pawn which can be captured en passant, which always belongs to the side not to move.
+const EN_PASSANT_PAWN: u8 = 14;
+
+const BLACK_TO_MOVE_FLAG: u8 = 1;
+const CHESS960_FLAG: u8 = 1 << 1;
+
+/// The number of bytes taken by a [`PackedBoard`].
+pub const PACKED_BOARD_SIZE: usize = 28;
+
+/// A compact encoding of a [`Board`], taking [`PACKED_BOARD_SIZE`] bytes.
+///
+/// The encoding starts with the occupation bitboard, in little endian, followed by a nibble for
+/// each occupied square, in order of increasing square index, starting with the low nibble.
+/// Castling rights are stored as codes for rooks that can castle, and the en passant square as
+/// a code for the pawn that can be captured en passant. The last four bytes hold a flag byte,
+/// for black being the side to move and for the Chess960 castling mode, the half-move clock and
+/// the full-move number, in little endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PackedBoard(pub [u8; PACKED_BOARD_SIZE]);
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum UnpackBoardError {
+    #[error("packed board must have at most 32 pieces")]
+    TooManyPieces,
+    #[error("packed board contains an invalid piece code")]
+    InvalidPieceCode,
+    #[error("packed board has a castling rook without a king able to castle with it")]
+    InvalidCastlingRights,
+    #[error("packed board must have at most one pawn that can be captured en passant")]
+    InvalidEnPassantPawn,
+    #[error("packed board is invalid")]
+    InvalidBoard(#[source] ParseBoardError),
+}
+
+/// A [`ChessMove`] packed into 16 bits: the origin square in the lowest 6 bits, then the target
+/// square, then 3 bits for the promotion, which are zero when there is none, and otherwise one
+/// more than the index of the promotion in [`PieceKind::ALL`]. Conversions from and to
+/// [`ChessMove`] are lossless.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PackedMove(pub u16);
+
+impl PackedMove {
+    /// A value which doesn't correspond to any move, as its promotion bits are all set.
+    pub const NULL: PackedMove = PackedMove(u16::MAX);
+
+    pub fn is_null(self) -> bool {
+        self == Self::NULL
+    }
+
+    /// Unpacks the move, returning `None` for [`PackedMove::NULL`] and for any other value not
+    /// produced by packing a move.
+    pub fn unpack(self) -> Option<ChessMove> {
+        let PackedMove(bits) = self;
+
+        if bits >> 15 != 0 {
+            return None;
+        }
+
+        let promotion = match bits >> 12 {
+            0 => None,
+            code => Some(*PieceKind::ALL.get(code as usize - 1)?),
+        };
+
+        Some(ChessMove {
+            origin: Square::ALL[bits as usize & 0x3f],
+            target: Square::ALL[(bits >> 6) as usize & 0x3f],
+            promotion,
+        })
+    }
+}
+
+impl From<ChessMove> for PackedMove {
+    fn from(chess_move: ChessMove) -> Self {
+        let promotion = chess_move
+            .promotion
+            .map_or(0, |kind| kind_index(kind) as u16 + 1);
+
+        PackedMove(
+            chess_move.origin.as_index() as u16
+                | (chess_move.target.as_index() as u16) << 6
+                | promotion << 12,
+        )
+    }
+}
+
+fn kind_index(kind: PieceKind) -> u8 {
+    PieceKind::ALL
+        .iter()
+        .position(|&other| other == kind)
+        .unwrap() as u8
+}
+
+// The rooks of a player which can still be used to castle. Castling rights may also be left on
+// other squares, which don't matter and aren't kept by packing.
+fn castling_rooks(player: &Player) -> BitBoard {
+    [CastlingSide::KingSide, CastlingSide::QueenSide]
+        .into_iter()
+        .filter_map(|side| player.castling_rook(side))
+        .fold(BitBoard::EMPTY, |rooks, square| {
+            rooks | BitBoard::from(square)
+        })
+}
+
+impl Board {
+    /// Packs the board into its compact encoding. See [`PackedBoard`].
+    pub fn to_packed(&self) -> PackedBoard {
+        let mut bytes = [0; PACKED_BOARD_SIZE];
+        let occupation = self.occupation();
+
+        bytes[..8].copy_from_slice(&occupation.0.to_le_bytes());
+
+        let castling_rooks = castling_rooks(&self.us) | castling_rooks(&self.them);
+        let en_passant_pawn_square = self
+            .en_passant_capture_square
+            .map(|square| BitBoard::from(square).move_one_down(self.playing_color));
+
+        for (index, square) in occupation.bits().enumerate() {
+            // SAFETY: The square is occupied
+            let piece = unsafe { self.piece(square).unwrap_unchecked() };
+            let code = if castling_rooks.get_bit(square) {
+                match piece.color {
+                    Color::White => WHITE_CASTLING_ROOK,
+                    Color::Black => BLACK_CASTLING_ROOK,
+                }
+            } else if en_passant_pawn_square == Some(BitBoard::from(square)) {
+                EN_PASSANT_PAWN
+            } else {
+                match piece.color {
+                    Color::White => WHITE,
+                    Color::Black => BLACK,
+                }
+                .wrapping_add(kind_index(piece.kind))
+            };
+
+            bytes[8 + index / 2] |= code << (4 * (index % 2));
+        }
+
+        bytes[24] = match self.playing_color {
+            Color::White => 0,
+            Color::Black => BLACK_TO_MOVE_FLAG,
+        } | match self.castling_mode {
+            CastlingMode::Standard => 0,
+            CastlingMode::Chess960 => CHESS960_FLAG,
+        };
+        bytes[25] = self.min_ply_clock;
+        bytes[26..].copy_from_slice(&self.full_moves.to_le_bytes());
+
+        PackedBoard(bytes)
+    }
+
+    /// Unpacks a board packed with [`Board::to_packed`], rebuilding the state derived from its
+    /// pieces, such as its hash, checkers and pinned pieces. The packed board is checked to be
+    /// valid, so it may come from any source.
+    pub fn from_packed(packed: &PackedBoard) -> Result<Self, UnpackBoardError> {
+        let PackedBoard(bytes) = packed;
+        let occupation = BitBoard(u64::from_le_bytes(bytes[..8].try_into().unwrap()));
+
+        if occupation.count_ones() > 32 {
+            return Err(UnpackBoardError::TooManyPieces);
+        }
+
+        let playing_color = if bytes[24] & BLACK_TO_MOVE_FLAG == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        let mut white = Player::blank();
+        let mut black = Player::blank();
+        let mut en_passant_capture_square = None;
+
+        for (index, square) in occupation.bits().enumerate() {
+            let code = (bytes[8 + index / 2] >> (4 * (index % 2))) & 0xf;
+
+            let (color, kind) = match code {
+                WHITE_CASTLING_ROOK => (Color::White, PieceKind::Rook),
+                BLACK_CASTLING_ROOK => (Color::Black, PieceKind::Rook),
+                EN_PASSANT_PAWN => {
+                    if en_passant_capture_square.is_some() {
+                        return Err(UnpackBoardError::InvalidEnPassantPawn);
+                    }
+
+                    en_passant_capture_square = BitBoard::from(square)
+                        .move_one_up(playing_color)
+                        .first_one_as_square();
+
+                    (!playing_color, PieceKind::Pawn)
+                }
+                code if code < BLACK => (Color::White, PieceKind::ALL[code as usize]),
+                code if code < WHITE_CASTLING_ROOK => {
+                    (Color::Black, PieceKind::ALL[(code - BLACK) as usize])
+                }
+                _ => return Err(UnpackBoardError::InvalidPieceCode),
+            };
+
+            let player = match color {
+                Color::White => &mut white,
+                Color::Black => &mut black,
+            };
+
+            player.toggle_piece(square, kind);
+
+            if code == WHITE_CASTLING_ROOK || code == BLACK_CASTLING_ROOK {
+                player.castling_rights[square] = true;
+            }
+        }
+
+        for (player, color) in [(&mut white, Color::White), (&mut black, Color::Black)] {
+            let castling_rooks = player
+                .rooks
+                .bits()
+                .filter(|&square| player.castling_rights[square])
+                .collect::<Vec<_>>();
+
+            if let Ok(king_square) = Square::try_from(player.king) {
+                let mut sides = [false; 2];
+
+                for rook_square in castling_rooks {
+                    let side = if rook_square.file() > king_square.file() {
+                        CastlingSide::KingSide
+                    } else {
+                        CastlingSide::QueenSide
+                    };
+
+                    if king_square.rank() != back_rank(color)
+                        || rook_square.rank() != back_rank(color)
+                        || sides[side as usize]
+                    {
+                        return Err(UnpackBoardError::InvalidCastlingRights);
+                    }
+
+                    sides[side as usize] = true;
+                    player.castling_rights[king_square] = true;
+                }
+            } else if !castling_rooks.is_empty() {
+                return Err(UnpackBoardError::InvalidCastlingRights);
+            }
+        }
+
+        Board::from_players(
+            white,
+            black,
+            playing_color,
+            en_passant_capture_square,
+            bytes[25],
+            u16::from_le_bytes([bytes[26], bytes[27]]),
+            if bytes[24] & CHESS960_FLAG == 0 {
+                CastlingMode::Standard
+            } else {
+                CastlingMode::Chess960
+            },
+        )
+        .map_err(UnpackBoardError::InvalidBoard)
+    }
+}
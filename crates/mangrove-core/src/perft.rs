@@ -0,0 +1,219 @@
+use std::{
+    ops::AddAssign,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    thread,
+};
+
+use mangrove_bootstrap::BitBoard;
+
+use crate::{
+    board::Board,
+    mg,
+    repr::{ChessMove, MoveKind},
+};
+
+/// A breakdown of the leaf nodes reached by a perft, by the kind of move leading to them. The
+/// categories match the reference tables of the Chess Programming Wiki.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    /// Checks given by a single piece other than the one moved. Double checks aren't included.
+    pub discovered_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+impl AddAssign for PerftStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.nodes += rhs.nodes;
+        self.captures += rhs.captures;
+        self.en_passants += rhs.en_passants;
+        self.castles += rhs.castles;
+        self.promotions += rhs.promotions;
+        self.checks += rhs.checks;
+        self.discovered_checks += rhs.discovered_checks;
+        self.double_checks += rhs.double_checks;
+        self.checkmates += rhs.checkmates;
+    }
+}
+
+impl Board {
+    /// Counts the leaf nodes reached by each legal move of the board, at the passed depth. This
+    /// is the same as [`Board::perft`], split by root move.
+    pub fn perft_divide(&self, depth: u32) -> Vec<(ChessMove, u64)> {
+        self.gen_child_boards()
+            .map(|(chess_move, child_board)| {
+                (chess_move, child_board.perft(depth.saturating_sub(1)))
+            })
+            .collect()
+    }
+
+    /// Like [`Board::perft`], but additionally counts the kinds of moves leading to the leaf
+    /// nodes.
+    pub fn perft_detailed(&self, depth: u32) -> PerftStats {
+        let mut stats = PerftStats::default();
+
+        if depth == 0 {
+            stats.nodes = 1;
+        } else if depth == 1 {
+            for (chess_move, child_board) in self.gen_child_boards() {
+                stats += self.leaf_stats(chess_move, &child_board);
+            }
+        } else {
+            for (_, child_board) in self.gen_child_boards() {
+                stats += child_board.perft_detailed(depth - 1);
+            }
+        }
+
+        stats
+    }
+
+    fn leaf_stats(&self, chess_move: ChessMove, child_board: &Board) -> PerftStats {
+        let mut stats = PerftStats {
+            nodes: 1,
+            ..PerftStats::default()
+        };
+
+        let move_kind = self.classify(chess_move);
+
+        if move_kind.is_capture() {
+            stats.captures = 1;
+        }
+
+        if move_kind == MoveKind::EnPassant {
+            stats.en_passants = 1;
+        }
+
+        if let MoveKind::Castle(_) = move_kind {
+            stats.castles = 1;
+        }
+
+        if move_kind.promotion().is_some() {
+            stats.promotions = 1;
+        }
+
+        if child_board.in_check() {
+            // The square of the piece which moved, which for castles is the rook, as the king
+            // can never give check
+            let moved_piece_square = match move_kind {
+                MoveKind::Castle(side) => side.rook_target(self.playing_color),
+                _ => chess_move.target,
+            };
+
+            stats.checks = 1;
+
+            if child_board.checkers.count_ones() > 1 {
+                stats.double_checks = 1;
+            } else if child_board.checkers != BitBoard::from(moved_piece_square) {
+                stats.discovered_checks = 1;
+            }
+
+            if mg::gen_moves(child_board).is_empty() {
+                stats.checkmates = 1;
+            }
+        }
+
+        stats
+    }
+
+    /// Like [`Board::perft`], but splits the root moves between the passed number of threads,
+    /// and caches the node counts of the positions it reaches in a table shared between them,
+    /// keyed by [`Board::hash`]. The table holds the passed number of entries, and is allocated
+    /// for each call.
+    ///
+    /// As positions are only identified by their hash, a collision could cause a wrong result,
+    /// although this is extremely unlikely.
+    pub fn perft_parallel(&self, depth: u32, threads: usize, cache_entries: usize) -> u64 {
+        if depth <= 1 {
+            return self.perft(depth);
+        }
+
+        let cache = PerftCache::new(cache_entries);
+        let child_boards = self.gen_child_boards().collect::<Vec<_>>();
+        let next_child = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            let workers = (0..threads.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut nodes = 0;
+
+                        while let Some((_, child_board)) =
+                            child_boards.get(next_child.fetch_add(1, Ordering::Relaxed))
+                        {
+                            nodes += child_board.perft_cached(depth - 1, &cache);
+                        }
+
+                        nodes
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .sum()
+        })
+    }
+
+    fn perft_cached(&self, depth: u32, cache: &PerftCache) -> u64 {
+        if depth <= 1 {
+            return self.perft(depth);
+        }
+
+        if let Some(nodes) = cache.get(self.hash, depth) {
+            return nodes;
+        }
+
+        let nodes = self
+            .gen_child_boards()
+            .map(|(_, child_board)| child_board.perft_cached(depth - 1, cache))
+            .sum();
+
+        cache.insert(self.hash, depth, nodes);
+
+        nodes
+    }
+}
+
+// A lockless table of node counts, shared between threads. Each entry stores its key xored with
+// its data, so that an entry torn by concurrent writes is detected instead of being trusted.
+struct PerftCache {
+    entries: Vec<[AtomicU64; 2]>,
+}
+
+impl PerftCache {
+    fn new(entries: usize) -> Self {
+        Self {
+            entries: (0..entries.max(1))
+                .map(|_| [AtomicU64::new(0), AtomicU64::new(0)])
+                .collect(),
+        }
+    }
+
+    fn entry(&self, hash: u64) -> &[AtomicU64; 2] {
+        &self.entries[(hash % self.entries.len() as u64) as usize]
+    }
+
+    // The depth is stored in the lowest byte of the data, and the node count in the rest
+    fn get(&self, hash: u64, depth: u32) -> Option<u64> {
+        let [key, data] = self.entry(hash);
+        let data = data.load(Ordering::Relaxed);
+
+        (key.load(Ordering::Relaxed) ^ data == hash && data & 0xff == depth as u64)
+            .then_some(data >> 8)
+    }
+
+    fn insert(&self, hash: u64, depth: u32, nodes: u64) {
+        let [key, data] = self.entry(hash);
+        let new_data = nodes << 8 | depth as u64;
+
+        key.store(hash ^ new_data, Ordering::Relaxed);
+        data.store(new_data, Ordering::Relaxed);
+    }
+}
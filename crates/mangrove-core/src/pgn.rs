@@ -0,0 +1,740 @@
+use std::{
+    fmt::{self, Display},
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use mangrove_bootstrap::Color;
+
+use crate::{
+    board::{Board, ParseBoardError},
+    game::{Game, Outcome},
+    repr::CastlingMode,
+    san::ParseSanError,
+};
+
+/// The maximum line length used when writing movetext, as recommended by the PGN standard.
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum PgnErrorKind {
+    #[error("failed to read input")]
+    Io(#[source] io::Error),
+    #[error("unexpected character `{0}`")]
+    UnexpectedCharacter(char),
+    #[error("unexpected token `{0}`")]
+    UnexpectedToken(String),
+    #[error("unexpected end of input")]
+    UnexpectedEof,
+    #[error("string is not terminated before the end of the line")]
+    UnterminatedString,
+    #[error("numeric annotation glyph should be an integer between 0 and 255")]
+    InvalidNag,
+    #[error("fen tag is invalid")]
+    InvalidFen(#[source] ParseBoardError),
+    #[error("move `{0}` is invalid")]
+    InvalidMove(String, #[source] ParseSanError),
+}
+
+/// An error found while reading a PGN game, along with the (one-based) line and column it was
+/// found at.
+#[derive(Debug, thiserror::Error)]
+#[error("{kind} at line {line}, column {column}")]
+pub struct ParsePgnError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PgnErrorKind,
+}
+
+/// The result of a game as recorded in PGN. Unlike [`Outcome`], this may also describe games
+/// which were adjudicated, resigned or left unfinished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Win(Color),
+    Draw,
+    /// The game is still in progress, was abandoned, or its result is otherwise unknown.
+    Unknown,
+}
+
+impl GameResult {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        Some(match symbol {
+            "1-0" => Self::Win(Color::White),
+            "0-1" => Self::Win(Color::Black),
+            "1/2-1/2" => Self::Draw,
+            "*" => Self::Unknown,
+            _ => return None,
+        })
+    }
+}
+
+impl From<Option<Outcome>> for GameResult {
+    fn from(outcome: Option<Outcome>) -> Self {
+        match outcome {
+            Some(Outcome::Win(color)) => Self::Win(color),
+            Some(Outcome::Draw(_)) => Self::Draw,
+            None => Self::Unknown,
+        }
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Win(Color::White) => "1-0",
+            Self::Win(Color::Black) => "0-1",
+            Self::Draw => "1/2-1/2",
+            Self::Unknown => "*",
+        })
+    }
+}
+
+/// The comment and numeric annotation glyphs (NAGs) following a move.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MoveAnnotation {
+    /// The text of the comment, with consecutive comments joined by a space when read. As a `}`
+    /// ends a comment in PGN, comments are written split at each `}` into consecutive comments,
+    /// so they are read back with the braces replaced by spaces.
+    pub comment: Option<String>,
+    pub nags: Vec<u8>,
+}
+
+/// A game read from or to be written as PGN.
+///
+/// Variations are not kept, only the main line of a game is.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    /// The tag pairs of the game, in order.
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+    /// The comment placed before the first move of the game, if any, written and read as
+    /// described in [`MoveAnnotation::comment`].
+    pub initial_comment: Option<String>,
+    /// The annotations of each move of the game, in the same order as [`Game::moves`].
+    pub annotations: Vec<MoveAnnotation>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    /// Returns the value of the first tag with the passed name, if there is one.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl From<Game> for PgnGame {
+    /// Wraps the passed game, using the seven tag roster with unknown values, and the outcome of
+    /// the game as its result. A `FEN` tag is added if the game doesn't start from the starting
+    /// position, and a `Variant` tag is added for Chess960 games.
+    fn from(game: Game) -> Self {
+        let result = GameResult::from(game.outcome());
+
+        let mut tags = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .to_vec();
+
+        tags.push(("Result".to_string(), result.to_string()));
+
+        if game.starting_board().castling_mode == CastlingMode::Chess960 {
+            tags.push(("Variant".to_string(), "Chess960".to_string()));
+        }
+
+        if *game.starting_board() != Board::starting_position() {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), game.starting_board().to_string()));
+        }
+
+        Self {
+            tags,
+            annotations: vec![MoveAnnotation::default(); game.moves().len()],
+            game,
+            initial_comment: None,
+            result,
+        }
+    }
+}
+
+fn push_comment(words: &mut Vec<String>, comment: &str) {
+    // Closing braces cannot be escaped inside of comments, so the comment is split at them
+    for part in comment.split('}') {
+        let mut comment_words = part.split_whitespace();
+
+        if let Some(first) = comment_words.next() {
+            words.push(format!("{{{first}"));
+            words.extend(comment_words.map(str::to_string));
+            words.last_mut().unwrap().push('}');
+        }
+    }
+}
+
+impl Display for PgnGame {
+    /// Formats the game in PGN export format, without a trailing newline.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(
+                f,
+                "[{name} \"{}\"]",
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )?;
+        }
+
+        if !self.tags.is_empty() {
+            writeln!(f)?;
+        }
+
+        let mut words = vec![];
+        let mut board = *self.game.starting_board();
+        // Black moves are given a move number at the start of the game, and after comments
+        let mut needs_move_number = true;
+
+        if let Some(comment) = &self.initial_comment {
+            push_comment(&mut words, comment);
+        }
+
+        for (index, &chess_move) in self.game.moves().iter().enumerate() {
+            match board.playing_color {
+                Color::White => words.push(format!("{}.", board.full_moves)),
+                Color::Black if needs_move_number => words.push(format!("{}...", board.full_moves)),
+                Color::Black => (),
+            }
+
+            words.push(board.move_to_san(chess_move));
+            board
+                .make_move(chess_move)
+                .expect("moves of a game should be legal");
+
+            needs_move_number = false;
+
+            if let Some(annotation) = self.annotations.get(index) {
+                words.extend(annotation.nags.iter().map(|nag| format!("${nag}")));
+
+                if let Some(comment) = &annotation.comment {
+                    push_comment(&mut words, comment);
+                    needs_move_number = true;
+                }
+            }
+        }
+
+        words.push(self.result.to_string());
+
+        let mut line_length = 0;
+
+        for word in words {
+            if line_length > 0 && line_length + 1 + word.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            } else if line_length > 0 {
+                f.write_str(" ")?;
+                line_length += 1;
+            }
+
+            f.write_str(&word)?;
+            line_length += word.len();
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    OpenBracket,
+    CloseBracket,
+    OpenParenthesis,
+    CloseParenthesis,
+    Period,
+    String(String),
+    Symbol(String),
+    Comment(String),
+    Nag(u8),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OpenBracket => f.write_str("["),
+            Self::CloseBracket => f.write_str("]"),
+            Self::OpenParenthesis => f.write_str("("),
+            Self::CloseParenthesis => f.write_str(")"),
+            Self::Period => f.write_str("."),
+            Self::String(string) => write!(f, "\"{string}\""),
+            Self::Symbol(symbol) => f.write_str(symbol),
+            Self::Comment(comment) => write!(f, "{{{comment}}}"),
+            Self::Nag(nag) => write!(f, "${nag}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    fn error(self, kind: PgnErrorKind) -> ParsePgnError {
+        ParsePgnError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+}
+
+fn is_symbol_continuation(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_+#=:-/!?".contains(c)
+}
+
+/// Splits PGN input into tokens, keeping track of where each token starts.
+struct Lexer<R> {
+    reader: R,
+    line: Vec<char>,
+    line_number: usize,
+    // The zero-based index of the next character in the current line
+    column: usize,
+    peeked: Option<Option<(Token, Position)>>,
+}
+
+impl<R: BufRead> Lexer<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: vec![],
+            line_number: 0,
+            column: 0,
+            peeked: None,
+        }
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            line: self.line_number,
+            column: self.column + 1,
+        }
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, ParsePgnError> {
+        while self.column >= self.line.len() {
+            let mut line = String::new();
+
+            if self
+                .reader
+                .read_line(&mut line)
+                .map_err(|error| self.position().error(PgnErrorKind::Io(error)))?
+                == 0
+            {
+                return Ok(None);
+            }
+
+            self.line = line.chars().collect();
+            self.line_number += 1;
+            self.column = 0;
+
+            // Lines starting with a percent sign are escaped, and should be ignored
+            if self.line.first() == Some(&'%') {
+                self.column = self.line.len();
+            }
+        }
+
+        Ok(Some(self.line[self.column]))
+    }
+
+    fn lex_string(&mut self) -> Result<String, PgnErrorKind> {
+        let mut string = String::new();
+
+        loop {
+            let c = match self.line.get(self.column) {
+                Some('\n' | '\r') | None => return Err(PgnErrorKind::UnterminatedString),
+                Some(&c) => c,
+            };
+
+            self.column += 1;
+
+            match c {
+                '"' => return Ok(string),
+                '\\' => match self.line.get(self.column) {
+                    Some(&escaped @ ('"' | '\\')) => {
+                        string.push(escaped);
+                        self.column += 1;
+                    }
+                    _ => string.push(c),
+                },
+                _ => string.push(c),
+            }
+        }
+    }
+
+    fn lex(&mut self) -> Result<Option<(Token, Position)>, ParsePgnError> {
+        while let Some(c) = self.peek_char()? {
+            if !c.is_whitespace() && c != '\u{feff}' {
+                break;
+            }
+
+            self.column += 1;
+        }
+
+        let position = self.position();
+        let Some(c) = self.peek_char()? else {
+            return Ok(None);
+        };
+
+        self.column += 1;
+
+        let token = match c {
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '(' => Token::OpenParenthesis,
+            ')' => Token::CloseParenthesis,
+            '.' => Token::Period,
+            '*' => Token::Symbol("*".to_string()),
+            '"' => Token::String(self.lex_string().map_err(|kind| position.error(kind))?),
+            '{' => {
+                let mut comment = String::new();
+
+                loop {
+                    match self.peek_char()? {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(position.error(PgnErrorKind::UnexpectedEof)),
+                    }
+
+                    self.column += 1;
+                }
+
+                self.column += 1;
+
+                Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            ';' => {
+                let comment = self.line[self.column..].iter().collect::<String>();
+                self.column = self.line.len();
+
+                Token::Comment(comment.trim().to_string())
+            }
+            '$' => {
+                let digits = self.line[self.column..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>();
+                self.column += digits.len();
+
+                Token::Nag(
+                    digits
+                        .parse()
+                        .map_err(|_| position.error(PgnErrorKind::InvalidNag))?,
+                )
+            }
+            c if c.is_ascii_alphanumeric() => {
+                let symbol = self.line[self.column - 1..]
+                    .iter()
+                    .take_while(|&&c| is_symbol_continuation(c))
+                    .collect::<String>();
+                self.column += symbol.len() - 1;
+
+                Token::Symbol(symbol)
+            }
+            c => return Err(position.error(PgnErrorKind::UnexpectedCharacter(c))),
+        };
+
+        Ok(Some((token, position)))
+    }
+
+    fn peek(&mut self) -> Result<Option<&(Token, Position)>, ParsePgnError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+
+        Ok(self.peeked.as_ref().unwrap().as_ref())
+    }
+
+    fn next(&mut self) -> Result<Option<(Token, Position)>, ParsePgnError> {
+        match self.peeked.take() {
+            Some(peeked) => Ok(peeked),
+            None => self.lex(),
+        }
+    }
+
+    fn expect(&mut self) -> Result<(Token, Position), ParsePgnError> {
+        self.next()?
+            .ok_or_else(|| self.position().error(PgnErrorKind::UnexpectedEof))
+    }
+}
+
+fn unexpected(token: Token, position: Position) -> ParsePgnError {
+    position.error(PgnErrorKind::UnexpectedToken(token.to_string()))
+}
+
+/// Reads games one at a time from a (possibly multi-game) PGN input.
+///
+/// A malformed game is reported as an error, after which reading resumes at the next game.
+/// The moves of (possibly nested) variations are checked to be legal, but variations aren't kept
+/// in the read games, and neither are their comments and NAGs.
+///
+/// # Example
+/// ```ignore
+/// let reader = PgnReader::new(BufReader::new(File::open("games.pgn")?));
+///
+/// for game in reader {
+///     match game {
+///         Ok(game) => println!("{:?}", game.result),
+///         Err(error) => eprintln!("skipping game: {error}"),
+///     }
+/// }
+/// ```
+pub struct PgnReader<R> {
+    lexer: Lexer<R>,
+    failed: bool,
+    // Whether the game being read has reached its movetext, which tells where it ends when it is
+    // malformed
+    in_movetext: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lexer: Lexer::new(reader),
+            failed: false,
+            in_movetext: false,
+        }
+    }
+
+    fn read_tag(&mut self) -> Result<(String, String, Position), ParsePgnError> {
+        let (name, position) = match self.lexer.expect()? {
+            (Token::Symbol(name), position) => (name, position),
+            (token, position) => return Err(unexpected(token, position)),
+        };
+
+        let value = match self.lexer.expect()? {
+            (Token::String(value), _) => value,
+            (token, position) => return Err(unexpected(token, position)),
+        };
+
+        match self.lexer.expect()? {
+            (Token::CloseBracket, _) => Ok((name, value, position)),
+            (token, position) => Err(unexpected(token, position)),
+        }
+    }
+
+    // Reads a variation up to its closing parenthesis, checking its moves from the passed board,
+    // which is the board before the move the variation replaces.
+    fn read_variation(&mut self, mut board: Board) -> Result<(), ParsePgnError> {
+        let mut previous_board = None;
+
+        loop {
+            let (token, position) = self.lexer.expect()?;
+
+            match token {
+                Token::Symbol(symbol) if GameResult::from_symbol(&symbol).is_none() => {
+                    if symbol.chars().all(|c| c.is_ascii_digit()) {
+                        continue;
+                    }
+
+                    let chess_move = board.parse_san(&symbol).map_err(|error| {
+                        position.error(PgnErrorKind::InvalidMove(symbol.clone(), error))
+                    })?;
+
+                    previous_board = Some(board);
+                    board
+                        .make_move(chess_move)
+                        .expect("parsed moves should be legal");
+                }
+                Token::Period | Token::Comment(_) | Token::Nag(_) => (),
+                Token::OpenParenthesis => match previous_board {
+                    Some(previous_board) => self.read_variation(previous_board)?,
+                    None => return Err(unexpected(token, position)),
+                },
+                Token::CloseParenthesis => return Ok(()),
+                _ => return Err(unexpected(token, position)),
+            }
+        }
+    }
+
+    fn read_game(&mut self) -> Result<PgnGame, ParsePgnError> {
+        let mut tags = vec![];
+        let mut board = None;
+        self.in_movetext = false;
+
+        while let Some((Token::OpenBracket, _)) = self.lexer.peek()? {
+            self.lexer.next()?;
+
+            let (name, value, position) = self.read_tag()?;
+
+            if name == "FEN" {
+                board = Some(
+                    Board::from_str(&value)
+                        .map_err(|error| position.error(PgnErrorKind::InvalidFen(error)))?,
+                );
+            }
+
+            tags.push((name, value));
+        }
+
+        let mut board = board.unwrap_or_else(Board::starting_position);
+
+        let is_chess960 = tags.iter().any(|(name, value)| {
+            name == "Variant"
+                && ["chess960", "chess 960", "fischerandom"]
+                    .contains(&value.to_lowercase().as_str())
+        });
+
+        if is_chess960 {
+            board.castling_mode = CastlingMode::Chess960;
+        }
+
+        let mut game = Game::new(board);
+        let mut initial_comment = None;
+        let mut annotations = Vec::<MoveAnnotation>::new();
+        // Variations replace the last move, so they start from the board before it
+        let mut previous_board = None;
+        self.in_movetext = true;
+
+        let result = loop {
+            let (token, position) = match self.lexer.peek()? {
+                // A missing game termination marker is tolerated at the end of the input, or
+                // before the tags of the next game
+                None | Some((Token::OpenBracket, _)) => break GameResult::Unknown,
+                Some(_) => self.lexer.next()?.unwrap(),
+            };
+
+            match token {
+                Token::Symbol(symbol) => {
+                    if let Some(result) = GameResult::from_symbol(&symbol) {
+                        break result;
+                    }
+
+                    // Move numbers are optional, and only used for readability
+                    if symbol.chars().all(|c| c.is_ascii_digit()) {
+                        continue;
+                    }
+
+                    let chess_move = game.board().parse_san(&symbol).map_err(|error| {
+                        position.error(PgnErrorKind::InvalidMove(symbol.clone(), error))
+                    })?;
+
+                    previous_board = Some(*game.board());
+                    game.make_move(chess_move)
+                        .expect("parsed moves should be legal");
+                    annotations.push(MoveAnnotation::default());
+                }
+                Token::Period => (),
+                Token::Comment(comment) => {
+                    let existing_comment = match annotations.last_mut() {
+                        Some(annotation) => &mut annotation.comment,
+                        None => &mut initial_comment,
+                    };
+
+                    match existing_comment {
+                        Some(existing_comment) => {
+                            existing_comment.push(' ');
+                            existing_comment.push_str(&comment);
+                        }
+                        None => *existing_comment = Some(comment),
+                    }
+                }
+                Token::Nag(nag) => match annotations.last_mut() {
+                    Some(annotation) => annotation.nags.push(nag),
+                    None => return Err(unexpected(token, position)),
+                },
+                Token::OpenParenthesis => match previous_board {
+                    Some(previous_board) => self.read_variation(previous_board)?,
+                    None => return Err(unexpected(token, position)),
+                },
+                _ => return Err(unexpected(token, position)),
+            }
+        };
+
+        Ok(PgnGame {
+            tags,
+            game,
+            initial_comment,
+            annotations,
+            result,
+        })
+    }
+
+    // Skips the rest of a malformed game, up to and including its termination marker, or up to
+    // the tags of the next game.
+    fn skip_game(&mut self) -> Result<(), ParsePgnError> {
+        let mut in_tag = false;
+        let mut in_movetext = self.in_movetext;
+
+        loop {
+            let token = match self.lexer.peek() {
+                Ok(Some((token, _))) => token.clone(),
+                Ok(None) => return Ok(()),
+                Err(error) if matches!(error.kind, PgnErrorKind::Io(_)) => return Err(error),
+                // Other lexing errors consume the offending input, so they can be ignored
+                Err(_) => continue,
+            };
+
+            if token == Token::OpenBracket && in_movetext {
+                return Ok(());
+            }
+
+            self.lexer.next()?;
+
+            match token {
+                Token::OpenBracket => in_tag = true,
+                Token::CloseBracket => in_tag = false,
+                Token::Symbol(symbol) if !in_tag && GameResult::from_symbol(&symbol).is_some() => {
+                    return Ok(())
+                }
+                _ if !in_tag => in_movetext = true,
+                _ => (),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, ParsePgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let game = match self.lexer.peek() {
+            Ok(None) => return None,
+            Ok(Some(_)) => self.read_game(),
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = &game {
+            // Input errors cannot be recovered from, but anything else only affects this game
+            self.failed = matches!(error.kind, PgnErrorKind::Io(_)) || self.skip_game().is_err();
+        }
+
+        Some(game)
+    }
+}
+
+/// Writes games in PGN export format, separating them with empty lines.
+pub struct PgnWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_game(&mut self, game: &PgnGame) -> io::Result<()> {
+        write!(self.writer, "{game}\n\n")
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -0,0 +1,150 @@
+use mangrove_bootstrap::{BitBoard, Color, Square};
+use rand::{seq::IteratorRandom, Rng};
+
+use crate::{
+    board::Board,
+    builder::BoardBuilder,
+    material::MaterialSignature,
+    repr::{back_rank, Piece, PieceKind},
+};
+
+/// The number of random setups tried before giving up on finding a position with the
+/// constraints.
+const MAX_ATTEMPTS: usize = 100_000;
+
+/// Whether generated positions should have the side to move in check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckConstraint {
+    #[default]
+    Any,
+    InCheck,
+    NotInCheck,
+}
+
+/// The constraints of the positions generated by [`random_position`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RandomPositionOptions {
+    pub material: MaterialSignature,
+    /// The side to move, or `None` to pick it at random.
+    pub playing_color: Option<Color>,
+    pub check: CheckConstraint,
+    /// Whether players keep their castling rights. Each player's king is then placed on its
+    /// starting square, and up to two of its rooks on the corners of its back rank, with the
+    /// right to castle with them.
+    pub castling: bool,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum RandomPositionError {
+    #[error("material doesn't fit on the board")]
+    TooManyPieces,
+    #[error("no legal position matching the constraints was found")]
+    NotFound,
+}
+
+/// Generates a random legal position with the passed constraints, placing the pieces uniformly on
+/// the squares they can be on and retrying until the board is valid. The same generator state
+/// always yields the same position.
+pub fn random_position(
+    rng: &mut impl Rng,
+    options: &RandomPositionOptions,
+) -> Result<Board, RandomPositionError> {
+    for color in [Color::White, Color::Black] {
+        let pieces = options.material.side(color);
+        let pawns = pieces
+            .iter()
+            .filter(|&&kind| kind == PieceKind::Pawn)
+            .count();
+
+        if pieces.len() > 15 || pawns > 8 {
+            return Err(RandomPositionError::TooManyPieces);
+        }
+    }
+
+    (0..MAX_ATTEMPTS)
+        .find_map(|_| random_setup(rng, options))
+        .ok_or(RandomPositionError::NotFound)
+}
+
+// Places the pieces at random, returning the board if it's legal and matches the constraints.
+fn random_setup(rng: &mut impl Rng, options: &RandomPositionOptions) -> Option<Board> {
+    let playing_color = options.playing_color.unwrap_or_else(|| {
+        if rng.gen() {
+            Color::White
+        } else {
+            Color::Black
+        }
+    });
+
+    let mut builder = BoardBuilder::new().playing_color(playing_color);
+    let mut occupied = BitBoard::EMPTY;
+
+    let mut remaining = [Color::White, Color::Black].map(|color| {
+        let mut pieces = vec![PieceKind::King];
+        pieces.extend_from_slice(options.material.side(color));
+        pieces
+    });
+
+    // The kings and castling rooks are placed first, so that no other piece takes their squares
+    if options.castling {
+        for color in [Color::White, Color::Black] {
+            let pieces = &mut remaining[color as usize];
+            let rank = back_rank(color);
+            let king_square = Square::from_file_and_rank(Square::E_FILE, rank);
+
+            builder = builder.place(
+                king_square,
+                Piece {
+                    kind: PieceKind::King,
+                    color,
+                },
+            );
+            occupied |= BitBoard::from(king_square);
+            pieces.remove(0);
+
+            for file in [Square::H_FILE, Square::A_FILE] {
+                let Some(index) = pieces.iter().position(|&kind| kind == PieceKind::Rook) else {
+                    break;
+                };
+
+                let rook_square = Square::from_file_and_rank(file, rank);
+
+                builder = builder
+                    .place(
+                        rook_square,
+                        Piece {
+                            kind: PieceKind::Rook,
+                            color,
+                        },
+                    )
+                    .castling_rook(color, rook_square);
+                occupied |= BitBoard::from(rook_square);
+                pieces.remove(index);
+            }
+        }
+    }
+
+    for color in [Color::White, Color::Black] {
+        for &kind in &remaining[color as usize] {
+            let square = Square::ALL
+                .into_iter()
+                .filter(|&square| {
+                    !(occupied.get_bit(square)
+                        || kind == PieceKind::Pawn && BitBoard::EDGE_RANKS.get_bit(square))
+                })
+                .choose(rng)?;
+
+            builder = builder.place(square, Piece { kind, color });
+            occupied |= BitBoard::from(square);
+        }
+    }
+
+    let board = builder.build().ok()?;
+
+    match options.check {
+        CheckConstraint::Any => Some(board),
+        CheckConstraint::InCheck => board.in_check().then_some(board),
+        CheckConstraint::NotInCheck => (!board.in_check()).then_some(board),
+    }
+}
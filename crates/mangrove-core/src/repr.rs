@@ -6,6 +6,11 @@ use std::{
 };
 
 #[derive(Eq, Hash, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 /// Represents a type of piece, such as a [king](`PieceKind::King`),
 /// or a [queen](`PieceKind::Queen`).
 pub enum PieceKind {
@@ -52,11 +57,21 @@ impl FromStr for PieceKind {
 }
 
 impl PieceKind {
+    /// An array of each kind of piece.
+    pub const ALL: [Self; 6] = [
+        Self::King,
+        Self::Queen,
+        Self::Rook,
+        Self::Bishop,
+        Self::Knight,
+        Self::Pawn,
+    ];
+
     /// An array of each piece a pawn can promote to.
     pub const PROMOTIONS: [Self; 4] = [Self::Queen, Self::Rook, Self::Bishop, Self::Knight];
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 /// Represents a Chess piece, which has a [type](`PieceKind`) and a [color](`Color`).
 pub struct Piece {
     pub kind: PieceKind,
@@ -241,27 +256,127 @@ impl FromStr for ChessMove {
     }
 }
 
+/// Moves are serialized in long algebraic notation, such as `e7e8q`, in human-readable formats,
+/// and as their [`PackedMove`](crate::packed::PackedMove) encoding otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ChessMove {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u16(crate::packed::PackedMove::from(*self).0)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ChessMove {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        if deserializer.is_human_readable() {
+            ChessMove::from_str(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+        } else {
+            crate::packed::PackedMove(u16::deserialize(deserializer)?)
+                .unpack()
+                .ok_or_else(|| D::Error::custom("packed move is null or invalid"))
+        }
+    }
+}
+
+/// The side of the board a castle happens on, relative to the king.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub struct CastlingRights([bool; 64]);
+pub enum CastlingSide {
+    KingSide,
+    QueenSide,
+}
 
-impl CastlingRights {
-    pub fn empty() -> Self {
-        Self([false; 64])
+impl CastlingSide {
+    pub const ALL: [Self; 2] = [Self::KingSide, Self::QueenSide];
+
+    /// Returns the square the king of the passed color ends up on after castling on this side.
+    /// This is the same in standard chess and in Chess960.
+    pub fn king_target(self, color: Color) -> Square {
+        let file = match self {
+            Self::KingSide => Square::G_FILE,
+            Self::QueenSide => Square::C_FILE,
+        };
+
+        Square::from_file_and_rank(file, back_rank(color))
     }
 
-    pub fn can_castle_king_side(&self) -> bool {
-        (self.0[Square::E1] ^ self.0[Square::E8]) && (self.0[Square::H1] ^ self.0[Square::H8])
+    /// Returns the square the rook of the passed color ends up on after castling on this side.
+    /// This is the same in standard chess and in Chess960.
+    pub fn rook_target(self, color: Color) -> Square {
+        let file = match self {
+            Self::KingSide => Square::F_FILE,
+            Self::QueenSide => Square::D_FILE,
+        };
+
+        Square::from_file_and_rank(file, back_rank(color))
     }
+}
 
-    pub fn can_castle_queen_side(&self) -> bool {
-        (self.0[Square::E1] ^ self.0[Square::E8]) && (self.0[Square::A1] ^ self.0[Square::A8])
+/// What a move does on the board it's made on, as found by [`Board::classify`].
+///
+/// [`Board::classify`]: crate::board::Board::classify
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MoveKind {
+    /// A move which captures nothing, including double pawn pushes.
+    Quiet,
+    Capture,
+    EnPassant,
+    Castle(CastlingSide),
+    /// A pawn promoting to the piece without capturing.
+    Promotion(PieceKind),
+    /// A pawn capturing and promoting to the piece.
+    CapturePromotion(PieceKind),
+}
+
+impl MoveKind {
+    pub fn is_capture(self) -> bool {
+        matches!(
+            self,
+            Self::Capture | Self::EnPassant | Self::CapturePromotion(_)
+        )
+    }
+
+    pub fn promotion(self) -> Option<PieceKind> {
+        match self {
+            Self::Promotion(kind) | Self::CapturePromotion(kind) => Some(kind),
+            _ => None,
+        }
     }
+}
 
-    pub fn as_minimized_rights(&self) -> usize {
-        self.0[Square::A1] as usize
-            | ((self.0[Square::H1] as usize) << 1)
-            | ((self.0[Square::A8] as usize) << 2)
-            | ((self.0[Square::H8] as usize) << 3)
+/// Returns the rank the pieces of the passed color start on.
+pub(crate) fn back_rank(color: Color) -> u8 {
+    match color {
+        Color::White => Square::RANK_1,
+        Color::Black => Square::RANK_8,
+    }
+}
+
+/// Describes how castles are encoded as [`ChessMove`]s.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum CastlingMode {
+    /// Castles are encoded as the king moving two squares, such as `e1g1`.
+    #[default]
+    Standard,
+    /// Castles are encoded as the king moving onto the castling rook, such as `e1h1`. This is
+    /// needed in Chess960, where the king may otherwise not move at all when castling.
+    Chess960,
+}
+
+/// Marks the starting squares of the king and rooks that can still be used to castle. Castling
+/// with a rook is only allowed when both its square and its king's square are marked, so moving
+/// the king takes away both castles at once.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CastlingRights([bool; 64]);
+
+impl CastlingRights {
+    pub fn empty() -> Self {
+        Self([false; 64])
     }
 }
 
@@ -332,6 +447,40 @@ impl Player {
         self.occupation.toggle_bit(square);
         self.piece_bitboard_mut(kind).toggle_bit(square);
     }
+
+    /// Returns the square of the rook this player can castle with on the passed side, if it still
+    /// has the right to. Whether the castle is currently possible isn't checked.
+    pub fn castling_rook(&self, side: CastlingSide) -> Option<Square> {
+        let king_square = Square::try_from(self.king).ok()?;
+
+        if !self.castling_rights[king_square] {
+            return None;
+        }
+
+        let is_castling_rook =
+            |&square: &Square| self.castling_rights[square] && self.rooks.get_bit(square);
+        let rank_square = |file| Square::from_file_and_rank(file, king_square.rank());
+
+        // Only a single rook should have castling rights on each side, but the outermost one is
+        // preferred regardless
+        match side {
+            CastlingSide::KingSide => (king_square.file() + 1..8)
+                .rev()
+                .map(rank_square)
+                .find(is_castling_rook),
+            CastlingSide::QueenSide => (0..king_square.file())
+                .map(rank_square)
+                .find(is_castling_rook),
+        }
+    }
+
+    pub fn can_castle_king_side(&self) -> bool {
+        self.castling_rook(CastlingSide::KingSide).is_some()
+    }
+
+    pub fn can_castle_queen_side(&self) -> bool {
+        self.castling_rook(CastlingSide::QueenSide).is_some()
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -0,0 +1,255 @@
+use std::str::FromStr;
+
+use mangrove_bootstrap::{BitBoard, Square};
+
+use crate::{
+    board::Board,
+    mg,
+    repr::{CastlingSide, ChessMove, PieceKind},
+};
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum ParseSanError {
+    #[error("move is not valid standard algebraic notation")]
+    Malformed,
+    #[error("move is illegal for used board")]
+    IllegalMove,
+    #[error("move matches more than one legal move of used board")]
+    AmbiguousMove,
+}
+
+fn piece_kind_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::King => 'K',
+        PieceKind::Queen => 'Q',
+        PieceKind::Rook => 'R',
+        PieceKind::Bishop => 'B',
+        PieceKind::Knight => 'N',
+        PieceKind::Pawn => 'P',
+    }
+}
+
+fn piece_kind_from_letter(letter: char) -> Option<PieceKind> {
+    Some(match letter {
+        'K' => PieceKind::King,
+        'Q' => PieceKind::Queen,
+        'R' => PieceKind::Rook,
+        'B' => PieceKind::Bishop,
+        'N' => PieceKind::Knight,
+        _ => return None,
+    })
+}
+
+fn file_char(file: u8) -> char {
+    (b'a' + file) as char
+}
+
+fn rank_char(rank: u8) -> char {
+    (b'1' + rank) as char
+}
+
+// The parts of a SAN move that don't depend on the board it is played on.
+struct SanParts {
+    kind: PieceKind,
+    origin_file: Option<u8>,
+    origin_rank: Option<u8>,
+    target: Square,
+    promotion: Option<PieceKind>,
+}
+
+impl FromStr for SanParts {
+    type Err = ParseSanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars().collect::<Vec<_>>();
+
+        let kind = match chars.first().copied().and_then(piece_kind_from_letter) {
+            Some(kind) => {
+                chars.remove(0);
+                kind
+            }
+            None => PieceKind::Pawn,
+        };
+
+        let promotion = match chars.last().copied().and_then(piece_kind_from_letter) {
+            Some(promotion) if kind == PieceKind::Pawn => {
+                chars.pop();
+
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+
+                Some(promotion)
+            }
+            _ => None,
+        };
+
+        if chars.len() < 2 {
+            return Err(ParseSanError::Malformed);
+        }
+
+        let target = Square::from_str(
+            &chars
+                .split_off(chars.len() - 2)
+                .into_iter()
+                .collect::<String>(),
+        )
+        .map_err(|_| ParseSanError::Malformed)?;
+
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+
+        let (origin_file, origin_rank) = match chars[..] {
+            [] => (None, None),
+            [file @ 'a'..='h'] => (Some(file as u8 - b'a'), None),
+            [rank @ '1'..='8'] => (None, Some(rank as u8 - b'1')),
+            [file @ 'a'..='h', rank @ '1'..='8'] => {
+                (Some(file as u8 - b'a'), Some(rank as u8 - b'1'))
+            }
+            _ => return Err(ParseSanError::Malformed),
+        };
+
+        Ok(Self {
+            kind,
+            origin_file,
+            origin_rank,
+            target,
+            promotion,
+        })
+    }
+}
+
+impl Board {
+    /// Formats the passed move in standard algebraic notation (SAN), such as `Nbd7`, `exd5`,
+    /// `O-O-O` or `e8=Q#`, relative to this board.
+    ///
+    /// The move is assumed to be legal for this board.
+    pub fn move_to_san(&self, chess_move: ChessMove) -> String {
+        let mut san = String::new();
+
+        if let Some(side) = self.castling_side(chess_move) {
+            san.push_str(match side {
+                CastlingSide::KingSide => "O-O",
+                CastlingSide::QueenSide => "O-O-O",
+            });
+        } else {
+            let kind = self.piece_kind_board[chess_move.origin].unwrap();
+            let is_capture = self.is_capture(chess_move);
+
+            if kind == PieceKind::Pawn {
+                if is_capture {
+                    san.push(file_char(chess_move.origin.file()));
+                }
+            } else {
+                san.push(piece_kind_letter(kind));
+
+                // Other pieces of the same kind that can move to the same square
+                let rivals = mg::gen_moves(self)
+                    .into_iter()
+                    .filter(|other| {
+                        other.target == chess_move.target
+                            && other.origin != chess_move.origin
+                            && self.piece_kind_board[other.origin] == Some(kind)
+                    })
+                    .fold(BitBoard::EMPTY, |rivals, other| {
+                        rivals | other.origin.into()
+                    });
+
+                if !rivals.is_empty() {
+                    let shares_file = rivals
+                        .bits()
+                        .any(|rival| rival.file() == chess_move.origin.file());
+                    let shares_rank = rivals
+                        .bits()
+                        .any(|rival| rival.rank() == chess_move.origin.rank());
+
+                    if !shares_file {
+                        san.push(file_char(chess_move.origin.file()));
+                    } else if !shares_rank {
+                        san.push(rank_char(chess_move.origin.rank()));
+                    } else {
+                        san.push(file_char(chess_move.origin.file()));
+                        san.push(rank_char(chess_move.origin.rank()));
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+
+            san.push_str(&chess_move.target.to_string());
+
+            if let Some(promotion) = chess_move.promotion {
+                san.push('=');
+                san.push(piece_kind_letter(promotion));
+            }
+        }
+
+        let mut new_board = *self;
+        new_board.make_move(chess_move).unwrap();
+
+        if new_board.in_check() {
+            san.push(if mg::gen_moves(&new_board).is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        san
+    }
+
+    /// Parses a move written in standard algebraic notation (SAN) relative to this board. Check
+    /// and annotation suffixes (such as `+`, `#` or `!?`) are accepted but ignored, as is the
+    /// capture marker. Castling may be written using either letter `O`s or digit `0`s.
+    pub fn parse_san(&self, san: &str) -> Result<ChessMove, ParseSanError> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let moves = mg::gen_moves(self);
+
+        let mut candidates = match san {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let side = if san.len() == 3 {
+                    CastlingSide::KingSide
+                } else {
+                    CastlingSide::QueenSide
+                };
+
+                moves
+                    .into_iter()
+                    .filter(|&chess_move| self.castling_side(chess_move) == Some(side))
+                    .collect::<Vec<_>>()
+            }
+            _ => {
+                let parts = SanParts::from_str(san)?;
+
+                moves
+                    .into_iter()
+                    .filter(|&chess_move| {
+                        self.piece_kind_board[chess_move.origin] == Some(parts.kind)
+                            && chess_move.target == parts.target
+                            && chess_move.promotion == parts.promotion
+                            && self.castling_side(chess_move).is_none()
+                            && parts
+                                .origin_file
+                                // Pawns stay on their file unless capturing, in which case the
+                                // file must be specified.
+                                .or((parts.kind == PieceKind::Pawn).then_some(parts.target.file()))
+                                .is_none_or(|file| chess_move.origin.file() == file)
+                            && parts
+                                .origin_rank
+                                .is_none_or(|rank| chess_move.origin.rank() == rank)
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        match candidates.len() {
+            0 => Err(ParseSanError::IllegalMove),
+            1 => Ok(candidates.pop().unwrap()),
+            _ => Err(ParseSanError::AmbiguousMove),
+        }
+    }
+}
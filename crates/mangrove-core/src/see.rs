@@ -0,0 +1,251 @@
+use arrayvec::ArrayVec;
+use mangrove_bootstrap::{BitBoard, Color, Square};
+
+use crate::{
+    board::Board,
+    repr::{back_rank, ChessMove, PieceKind},
+};
+
+/// The material values of each kind of piece, used by static exchange evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceValues {
+    pub pawn: i32,
+    pub knight: i32,
+    pub bishop: i32,
+    pub rook: i32,
+    pub queen: i32,
+    pub king: i32,
+}
+
+impl Default for PieceValues {
+    fn default() -> Self {
+        Self {
+            pawn: 100,
+            knight: 300,
+            bishop: 300,
+            rook: 500,
+            queen: 900,
+            king: 20000,
+        }
+    }
+}
+
+impl PieceValues {
+    pub fn value(&self, kind: PieceKind) -> i32 {
+        match kind {
+            PieceKind::King => self.king,
+            PieceKind::Queen => self.queen,
+            PieceKind::Rook => self.rook,
+            PieceKind::Bishop => self.bishop,
+            PieceKind::Knight => self.knight,
+            PieceKind::Pawn => self.pawn,
+        }
+    }
+}
+
+// The order in which pieces are used to capture, from least to most valuable.
+const CAPTURE_ORDER: [PieceKind; 6] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+    PieceKind::King,
+];
+
+impl Board {
+    /// Statically evaluates the exchange started by the passed move on its target square, using
+    /// the default [`PieceValues`]. See [`Board::see_with`].
+    pub fn see(&self, chess_move: ChessMove) -> i32 {
+        self.see_with(chess_move, &PieceValues::default())
+    }
+
+    /// Statically evaluates the exchange started by the passed move on its target square,
+    /// returning the material the side to move gains from it, assuming both sides keep
+    /// recapturing with their least valuable piece for as long as it is profitable.
+    ///
+    /// Attackers revealed by sliders moving off the capture line are taken into account, as are
+    /// en passant and promotions, with recapturing pawns always promoting to a queen. Pins are
+    /// ignored, but the king never recaptures onto a square which is still attacked. Castles
+    /// evaluate to zero. The move is assumed to be legal for this board.
+    pub fn see_with(&self, chess_move: ChessMove, values: &PieceValues) -> i32 {
+        if self.castling_side(chess_move).is_some() {
+            return 0;
+        }
+
+        let (first_gain, mut on_target, mut occupation) = self.exchange_start(chess_move, values);
+        let target = chess_move.target;
+
+        // The material gained by each capture in the sequence, from the view of its capturer. No
+        // more captures can be made than there are pieces on the board.
+        let mut gains = ArrayVec::<i32, 32>::new();
+        gains.push(first_gain);
+
+        let mut color = self.playing_color;
+
+        loop {
+            color = !color;
+
+            let Some((square, kind)) = self.least_valuable_attacker(target, occupation, color)
+            else {
+                break;
+            };
+
+            let mut gain = on_target - gains.last().unwrap();
+            on_target = values.value(kind);
+
+            if kind == PieceKind::Pawn && target.rank() == back_rank(!color) {
+                gain += values.queen - values.pawn;
+                on_target = values.queen;
+            }
+
+            gains.push(gain);
+            occupation ^= BitBoard::from(square);
+        }
+
+        // Each side only makes its capture if it is better than standing pat
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.last_mut().unwrap();
+
+            *previous = (*previous).min(-last);
+        }
+
+        gains[0]
+    }
+
+    /// Returns whether the static exchange evaluation of the passed move, using the default
+    /// [`PieceValues`], is at least the passed threshold.
+    pub fn see_ge(&self, chess_move: ChessMove, threshold: i32) -> bool {
+        self.see_ge_with(chess_move, threshold, &PieceValues::default())
+    }
+
+    /// Returns whether the static exchange evaluation of the passed move is at least the passed
+    /// threshold. See [`Board::see_with`].
+    ///
+    /// Unlike comparing the result of [`Board::see_with`], the exchange is only followed until
+    /// one side comes out ahead of the threshold even if the piece it captured with is lost.
+    pub fn see_ge_with(&self, chess_move: ChessMove, threshold: i32, values: &PieceValues) -> bool {
+        if self.castling_side(chess_move).is_some() {
+            return threshold <= 0;
+        }
+
+        let target = chess_move.target;
+
+        // A pawn recapturing onto the last rank gains more than the piece it captures, so losing
+        // the capturing piece isn't the worst that can happen there
+        if target.rank() == back_rank(Color::White) || target.rank() == back_rank(Color::Black) {
+            return self.see_with(chess_move, values) >= threshold;
+        }
+
+        let (first_gain, mut on_target, mut occupation) = self.exchange_start(chess_move, values);
+
+        // The balance relative to the threshold, from the view of the side which captured last,
+        // assuming the piece it captured with is lost
+        let mut balance = first_gain - threshold;
+
+        if balance < 0 {
+            return false;
+        }
+
+        balance -= on_target;
+
+        if balance >= 0 {
+            return true;
+        }
+
+        let mut color = self.playing_color;
+
+        loop {
+            color = !color;
+
+            let Some((square, kind)) = self.least_valuable_attacker(target, occupation, color)
+            else {
+                break;
+            };
+
+            balance = -balance;
+            on_target = values.value(kind);
+            balance -= on_target;
+
+            // The side to move wins ties, as reaching the threshold is enough
+            let ahead = if color == self.playing_color {
+                balance >= 0
+            } else {
+                balance > 0
+            };
+
+            if ahead {
+                return color == self.playing_color;
+            }
+
+            occupation ^= BitBoard::from(square);
+        }
+
+        // The side which captured last keeps its piece
+        color != self.playing_color
+    }
+
+    // Makes the first capture of the exchange started by the passed move, returning the material
+    // it gains, the value of the piece left on the target square, and the occupation without the
+    // moved piece and any pawn captured en passant.
+    fn exchange_start(&self, chess_move: ChessMove, values: &PieceValues) -> (i32, i32, BitBoard) {
+        let target = chess_move.target;
+        let moved_piece_kind = self.piece_kind_board[chess_move.origin].unwrap();
+
+        let mut occupation = self.occupation() ^ BitBoard::from(chess_move.origin);
+
+        let mut gain = match self.piece_kind_board[target] {
+            Some(kind) => values.value(kind),
+            None if moved_piece_kind == PieceKind::Pawn
+                && chess_move.origin.file() != target.file() =>
+            {
+                // The captured pawn is just behind the target square
+                occupation ^= BitBoard::from(target).move_one_down(self.playing_color);
+
+                values.pawn
+            }
+            None => 0,
+        };
+
+        let on_target = match chess_move.promotion {
+            Some(promotion) => {
+                gain += values.value(promotion) - values.pawn;
+                values.value(promotion)
+            }
+            None => values.value(moved_piece_kind),
+        };
+
+        (gain, on_target, occupation)
+    }
+
+    // Returns the least valuable piece of the passed color attacking the target square with the
+    // passed occupation. The king is only returned if the square isn't attacked by the other
+    // color once it captures.
+    fn least_valuable_attacker(
+        &self,
+        target: Square,
+        occupation: BitBoard,
+        color: Color,
+    ) -> Option<(Square, PieceKind)> {
+        let player = self.player(color);
+        let attackers = self.attackers_to(target, occupation) & player.occupation;
+
+        let (square, kind) = CAPTURE_ORDER.into_iter().find_map(|kind| {
+            let square = (attackers & player.piece_bitboard(kind)).first_one_as_square();
+
+            square.map(|square| (square, kind))
+        })?;
+
+        let occupation_after = occupation ^ BitBoard::from(square);
+
+        if kind == PieceKind::King
+            && !(self.attackers_to(target, occupation_after) & self.player(!color).occupation)
+                .is_empty()
+        {
+            return None;
+        }
+
+        Some((square, kind))
+    }
+}
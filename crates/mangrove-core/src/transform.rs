@@ -0,0 +1,106 @@
+use mangrove_bootstrap::{BitBoard, Color, Square};
+
+use crate::{
+    board::Board,
+    repr::{CastlingRights, CastlingSide, ChessMove, Player},
+};
+
+// Moves every piece and castling right of the player to its transformed square.
+fn transform_player(
+    player: &Player,
+    transform_bitboard: fn(BitBoard) -> BitBoard,
+    transform_square: fn(&Square) -> Square,
+) -> Player {
+    let mut castling_rights = CastlingRights::empty();
+
+    for square in Square::ALL {
+        castling_rights[transform_square(&square)] = player.castling_rights[square];
+    }
+
+    Player {
+        king: transform_bitboard(player.king),
+        queens: transform_bitboard(player.queens),
+        rooks: transform_bitboard(player.rooks),
+        bishops: transform_bitboard(player.bishops),
+        knights: transform_bitboard(player.knights),
+        pawns: transform_bitboard(player.pawns),
+        occupation: transform_bitboard(player.occupation),
+        castling_rights,
+    }
+}
+
+impl Board {
+    /// Returns the board with the colors of all pieces swapped and the ranks mirrored, so that
+    /// the position is the same from the view of the other side. Castling rights, the en passant
+    /// square and the side to move are transformed as well, and the hash is recomputed.
+    pub fn flipped(&self) -> Board {
+        let flip =
+            |player| transform_player(player, BitBoard::vertical_flip, Square::vertical_flip);
+
+        Board::from_players(
+            flip(self.player(Color::Black)),
+            flip(self.player(Color::White)),
+            !self.playing_color,
+            self.en_passant_capture_square
+                .map(|square| square.vertical_flip()),
+            self.min_ply_clock,
+            self.full_moves,
+            self.castling_mode,
+        )
+        .expect("flipping a valid board keeps it valid")
+    }
+
+    /// Returns the board with the files mirrored, such that the A file becomes the H file and
+    /// vice versa, or `None` if either side can still castle, as castling isn't symmetric.
+    pub fn mirrored_horizontally(&self) -> Option<Board> {
+        let can_castle = |player: &Player| {
+            [CastlingSide::KingSide, CastlingSide::QueenSide]
+                .into_iter()
+                .any(|side| player.castling_rook(side).is_some())
+        };
+
+        if can_castle(&self.us) || can_castle(&self.them) {
+            return None;
+        }
+
+        let mirror = |player| Player {
+            castling_rights: CastlingRights::empty(),
+            ..transform_player(player, BitBoard::horizontal_flip, Square::horizontal_flip)
+        };
+
+        Some(
+            Board::from_players(
+                mirror(self.player(Color::White)),
+                mirror(self.player(Color::Black)),
+                self.playing_color,
+                self.en_passant_capture_square
+                    .map(|square| square.horizontal_flip()),
+                self.min_ply_clock,
+                self.full_moves,
+                self.castling_mode,
+            )
+            .expect("mirroring a valid board keeps it valid"),
+        )
+    }
+}
+
+impl ChessMove {
+    /// Returns the move with its squares mirrored vertically, matching [`Board::flipped`].
+    pub fn flipped(&self) -> ChessMove {
+        ChessMove {
+            origin: self.origin.vertical_flip(),
+            target: self.target.vertical_flip(),
+            promotion: self.promotion,
+        }
+    }
+
+    /// Returns the move with its squares mirrored horizontally, matching
+    /// [`Board::mirrored_horizontally`].
+    pub fn mirrored_horizontally(&self) -> ChessMove {
+        ChessMove {
+            origin: self.origin.horizontal_flip(),
+            target: self.target.horizontal_flip(),
+            promotion: self.promotion,
+        }
+    }
+}
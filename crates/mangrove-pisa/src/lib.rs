@@ -45,10 +45,10 @@ fn final_board_to_tensor<B: Backend>(board: &Board) -> Tensor<B, 3> {
         vec![
             player_to_tensor(&board.us),
             player_to_tensor(&board.them),
-            boolean_to_tensor(board.us.castling_rights.can_castle_king_side()).unsqueeze(),
-            boolean_to_tensor(board.us.castling_rights.can_castle_queen_side()).unsqueeze(),
-            boolean_to_tensor(board.them.castling_rights.can_castle_king_side()).unsqueeze(),
-            boolean_to_tensor(board.them.castling_rights.can_castle_queen_side()).unsqueeze(),
+            boolean_to_tensor(board.us.can_castle_king_side()).unsqueeze(),
+            boolean_to_tensor(board.us.can_castle_queen_side()).unsqueeze(),
+            boolean_to_tensor(board.them.can_castle_king_side()).unsqueeze(),
+            boolean_to_tensor(board.them.can_castle_queen_side()).unsqueeze(),
             match board.playing_color {
                 Color::White => Tensor::ones(Shape::new([8, 8])),
                 Color::Black => Tensor::ones(Shape::new([8, 8])).neg(),
@@ -4,7 +4,7 @@ use std::{
 };
 
 use burn::tensor::backend::Backend;
-use mangrove_core::{board::Board, mg, repr::ChessMove};
+use mangrove_core::{board::Board, mg, packed::PackedMove, repr::ChessMove};
 use mangrove_pisa::Pisa;
 use ringbuffer::{AllocRingBuffer, RingBuffer};
 
@@ -17,7 +17,14 @@ pub(crate) struct TreeNodeMetadata {
     pub(crate) value_sum: f32,
     pub(crate) visits: u32,
     pub(crate) probability: f32,
-    chess_move: ChessMove,
+    packed_move: PackedMove,
+}
+
+impl TreeNodeMetadata {
+    fn chess_move(&self) -> ChessMove {
+        // Children are only created for legal moves, so their move is never null
+        self.packed_move.unpack().unwrap()
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -84,10 +91,11 @@ impl Tree {
     }
 
     pub fn try_advance(&mut self, chess_move: ChessMove) -> Result<(), AdvanceTreeError> {
+        let packed_move = PackedMove::from(chess_move);
         let next_root_index = self
             .get_children_metadata(&self.root())
             .ok_or(AdvanceTreeError::NotExpandedError)?
-            .find(|(_, child_metadata)| child_metadata.chess_move == chess_move)
+            .find(|(_, child_metadata)| child_metadata.packed_move == packed_move)
             .ok_or(AdvanceTreeError::IllegalMove)?
             .0;
 
@@ -105,7 +113,7 @@ impl Tree {
                     children
                         .max_by_key(|(_, child_metadata)| child_metadata.visits)?
                         .1
-                        .chess_move,
+                        .chess_move(),
                 )
             })
     }
@@ -143,7 +151,7 @@ impl Tree {
                     value_sum: 0.0,
                     visits: 0,
                     probability,
-                    chess_move,
+                    packed_move: PackedMove::from(chess_move),
                 }),
             })
         }) {
@@ -177,7 +185,7 @@ impl Tree {
                         // SAFETY: Children always have initialized metadata
                         last_node.metadata.assume_init_ref()
                     }
-                    .chess_move,
+                    .chess_move(),
                 )
                 .unwrap();
             history.push(current_board);
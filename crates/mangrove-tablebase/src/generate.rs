@@ -0,0 +1,185 @@
+use mangrove_bootstrap::Color;
+use mangrove_core::{material::MaterialSignature, mg, repr::PieceKind};
+
+use crate::{position::Indexer, table::Table, Tablebase};
+
+// The position is legal and canonical.
+const VALID: u8 = 1 << 0;
+// A move of the position leaves the table into a drawn position, or there are no moves.
+const DRAW_EXIT: u8 = 1 << 1;
+// A move of the position leaves the table into a position lost for the opponent.
+const WIN_EXIT: u8 = 1 << 2;
+
+/// Solves the positions of a material by retrograde analysis, using the tables of its successors
+/// to value the moves which capture or promote.
+///
+/// Every legal position is first given the number of its distinct moves staying in the table,
+/// and the positions decided by checkmate or by moves leaving the table are scheduled at their
+/// number of plies until mate. Positions are then resolved in increasing order of plies, and the
+/// moves leading to each are taken back: a lost position makes all its predecessors won, while a
+/// won position decrements the moves left to refute of its predecessors, which are lost once none
+/// are left. The remaining positions are drawn.
+pub(crate) fn generate(material: MaterialSignature, tablebase: &Tablebase) -> Table {
+    let indexer = Indexer::new(&material);
+    let size = indexer.size();
+
+    let mut values = vec![0u8; size];
+    let mut flags = vec![0u8; size];
+    let mut counters = vec![0u8; size];
+    let mut exit_losses = vec![0u8; size];
+    let mut buckets: Vec<Vec<u32>> = vec![];
+    let mut indices = vec![];
+
+    let schedule = |buckets: &mut Vec<Vec<u32>>, plies: u8, index: usize| {
+        if buckets.len() <= plies as usize {
+            buckets.resize(plies as usize + 1, vec![]);
+        }
+
+        buckets[plies as usize].push(index as u32);
+    };
+
+    for index in 0..size {
+        let position = indexer.position(index);
+
+        if indexer.index(&position) != index {
+            continue;
+        }
+
+        let Some(board) = indexer.board(&position) else {
+            continue;
+        };
+
+        flags[index] = VALID;
+
+        let moves = mg::gen_moves(&board);
+
+        if moves.is_empty() {
+            if board.in_check() {
+                schedule(&mut buckets, 0, index);
+            } else {
+                flags[index] |= DRAW_EXIT;
+            }
+
+            continue;
+        }
+
+        let mut win_exit = None::<u8>;
+        indices.clear();
+
+        for chess_move in moves {
+            if chess_move.promotion.is_none() && board.piece(chess_move.target).is_none() {
+                let child = position.with_move(
+                    chess_move.origin.as_index() as u8,
+                    chess_move.target.as_index() as u8,
+                );
+
+                indices.push(indexer.index(&child) as u32);
+                continue;
+            }
+
+            let mut child = board;
+            child.make_move(chess_move).unwrap();
+
+            let value = tablebase
+                .value(&child)
+                .expect("tables of successors should be generated first");
+
+            match value.checked_sub(1) {
+                None => flags[index] |= DRAW_EXIT,
+                Some(plies) if plies % 2 == 0 => {
+                    win_exit = Some(win_exit.map_or(plies + 1, |exit| exit.min(plies + 1)));
+                }
+                Some(plies) => exit_losses[index] = exit_losses[index].max(plies + 1),
+            }
+        }
+
+        indices.sort_unstable();
+        indices.dedup();
+        counters[index] = indices.len() as u8;
+
+        if let Some(plies) = win_exit {
+            flags[index] |= WIN_EXIT;
+            schedule(&mut buckets, plies, index);
+        } else if indices.is_empty() && flags[index] & DRAW_EXIT == 0 {
+            schedule(&mut buckets, exit_losses[index], index);
+        }
+    }
+
+    let mut plies = 0;
+
+    while (plies as usize) < buckets.len() {
+        for index in std::mem::take(&mut buckets[plies as usize]) {
+            let index = index as usize;
+
+            if values[index] != 0 {
+                continue;
+            }
+
+            values[index] = plies + 1;
+            indexer.predecessors(&indexer.position(index), &mut indices);
+
+            for &predecessor in &indices {
+                let predecessor = predecessor as usize;
+
+                if flags[predecessor] & VALID == 0 || values[predecessor] != 0 {
+                    continue;
+                }
+
+                if plies % 2 == 0 {
+                    schedule(&mut buckets, plies + 1, predecessor);
+                } else if flags[predecessor] & (WIN_EXIT | DRAW_EXIT) == 0 {
+                    counters[predecessor] -= 1;
+
+                    if counters[predecessor] == 0 {
+                        let loss = exit_losses[predecessor].max(plies + 1);
+                        schedule(&mut buckets, loss, predecessor);
+                    }
+                }
+            }
+        }
+
+        plies = plies
+            .checked_add(1)
+            .expect("mates should be shorter than 255 plies");
+    }
+
+    Table::new(material, indexer, values)
+}
+
+/// Returns the materials reachable with a single capture or promotion, which must be solved before
+/// this one. The materials of the kings alone are left out, as they are always drawn.
+pub(crate) fn successors(material: &MaterialSignature) -> Vec<MaterialSignature> {
+    let mut successors = vec![];
+
+    for (color, kind) in material.pieces() {
+        let mut replacements = vec![None];
+
+        if kind == PieceKind::Pawn {
+            replacements.extend(PieceKind::PROMOTIONS.map(Some));
+        }
+
+        for replacement in replacements {
+            let mut successor = material.clone();
+            let side = match color {
+                Color::White => &mut successor.white,
+                Color::Black => &mut successor.black,
+            };
+            let index = side.iter().position(|&other| other == kind).unwrap();
+
+            match replacement {
+                Some(promotion) => side[index] = promotion,
+                None => {
+                    side.remove(index);
+                }
+            }
+
+            successor.sort();
+
+            if successor.piece_count() > 0 && !successors.contains(&successor) {
+                successors.push(successor);
+            }
+        }
+    }
+
+    successors
+}
@@ -0,0 +1,504 @@
+//! Endgame tablebases for positions with few pieces, generated by retrograde analysis. Each table
+//! stores the number of plies until mate with best play for every position of a material, from
+//! which probing finds whether a position is won, drawn or lost, along with its best move.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use mangrove_core::{
+    board::Board,
+    material::MaterialSignature,
+    mg,
+    repr::{CastlingSide, ChessMove, PieceKind},
+};
+
+use crate::{
+    position::Position,
+    table::{ReadTableError, Table},
+};
+
+mod generate;
+mod position;
+pub mod syzygy;
+pub mod table;
+
+/// The extension of table files, whose names are their material.
+const TABLE_EXTENSION: &str = "mgtb";
+
+/// The maximum number of pieces other than the kings in a generated table.
+pub const MAX_PIECES: usize = 2;
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum GenerateTableError {
+    #[error("materials with pawns on both sides aren't supported, as they allow en passant")]
+    PawnsOnBothSides,
+    #[error("material may contain at most {MAX_PIECES} pieces other than the kings")]
+    TooManyPieces,
+}
+
+/// The result of a position with best play, from the view of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The result of probing a position of a [`Tablebase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Probe {
+    pub wdl: Wdl,
+    /// The number of plies until mate with best play, or `None` if the position is drawn.
+    pub plies_to_mate: Option<u8>,
+    /// A move keeping the result of the position and reaching mate the soonest when winning, or
+    /// delaying it the most when losing. It is `None` if there are no legal moves.
+    pub best_move: Option<ChessMove>,
+}
+
+/// A collection of tables, each solving the positions of a single material. Positions with the
+/// kings alone are always drawn, and positions with the colors of a table flipped are probed in
+/// it as well.
+#[derive(Debug, Clone, Default)]
+pub struct Tablebase {
+    tables: HashMap<MaterialSignature, Table>,
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates the table of the material, along with the ones of every material it can reach
+    /// through captures and promotions. Tables already in the tablebase are not generated again.
+    pub fn generate(&mut self, material: &MaterialSignature) -> Result<(), GenerateTableError> {
+        if self.table(material).is_some() {
+            return Ok(());
+        } else if material.piece_count() > MAX_PIECES {
+            return Err(GenerateTableError::TooManyPieces);
+        } else if material.white.contains(&PieceKind::Pawn)
+            && material.black.contains(&PieceKind::Pawn)
+        {
+            return Err(GenerateTableError::PawnsOnBothSides);
+        }
+
+        for successor in generate::successors(material) {
+            self.generate(&successor)?;
+        }
+
+        let table = generate::generate(material.clone(), self);
+        self.insert(table);
+
+        Ok(())
+    }
+
+    /// Adds a table to the tablebase, replacing any table of the same material.
+    pub fn insert(&mut self, table: Table) {
+        self.tables.insert(table.material().clone(), table);
+    }
+
+    /// Returns the table of the material or of its flipped colors, if there is one.
+    pub fn table(&self, material: &MaterialSignature) -> Option<&Table> {
+        self.tables
+            .get(material)
+            .or_else(|| self.tables.get(&material.flipped()))
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    // Returns the stored value of the board, as described in `Table`, or `None` if it isn't in
+    // the tablebase.
+    pub(crate) fn value(&self, board: &Board) -> Option<u8> {
+        for player in [&board.us, &board.them] {
+            if CastlingSide::ALL
+                .into_iter()
+                .any(|side| player.castling_rook(side).is_some())
+            {
+                return None;
+            }
+        }
+
+        let material = MaterialSignature::of_board(board);
+
+        if material.piece_count() == 0 {
+            Some(0)
+        } else if material.piece_count() > MAX_PIECES {
+            None
+        } else if let Some(table) = self.tables.get(&material) {
+            Some(table.value(&Position::from_board(board, &material)))
+        } else {
+            let table = self.tables.get(&material.flipped())?;
+            let board = board.flipped();
+
+            Some(table.value(&Position::from_board(&board, table.material())))
+        }
+    }
+
+    /// Probes the board, returning `None` if its material isn't in the tablebase or if either side
+    /// can still castle.
+    pub fn probe(&self, board: &Board) -> Option<Probe> {
+        let plies_to_mate = self.value(board)?.checked_sub(1);
+
+        // Moves are ordered by the plies until mate when the opponent is lost, then by draws,
+        // and then by the plies until mate in reverse when the opponent wins.
+        let best_move = mg::gen_moves(board).into_iter().min_by_key(|&chess_move| {
+            let mut child = *board;
+            child.make_move(chess_move).unwrap();
+
+            match self.value(&child).and_then(|value| value.checked_sub(1)) {
+                Some(plies) if plies % 2 == 0 => (0, plies),
+                None => (1, 0),
+                Some(plies) => (2, u8::MAX - plies),
+            }
+        });
+
+        Some(Probe {
+            wdl: match plies_to_mate {
+                None => Wdl::Draw,
+                Some(plies) if plies % 2 == 0 => Wdl::Loss,
+                Some(_) => Wdl::Win,
+            },
+            plies_to_mate,
+            best_move,
+        })
+    }
+
+    /// Writes each table into the directory, in a file named after its material.
+    pub fn save(&self, directory: impl AsRef<Path>) -> io::Result<()> {
+        for table in self.tables() {
+            let path = directory
+                .as_ref()
+                .join(format!("{}.{TABLE_EXTENSION}", table.material()));
+
+            let mut writer = BufWriter::new(File::create(path)?);
+            table.write(&mut writer)?;
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads every table file in the directory.
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self, ReadTableError> {
+        let mut tablebase = Self::new();
+
+        for entry in fs::read_dir(directory).map_err(ReadTableError::Io)? {
+            let path = entry.map_err(ReadTableError::Io)?.path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == TABLE_EXTENSION)
+            {
+                let file = File::open(path).map_err(ReadTableError::Io)?;
+                tablebase.insert(Table::read(BufReader::new(file))?);
+            }
+        }
+
+        Ok(tablebase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, sync::OnceLock};
+
+    use mangrove_core::{board::Board, material::MaterialSignature, mg};
+    use test_case::test_case;
+
+    use crate::{
+        position::Indexer,
+        syzygy::{Syzygy, WdlScore},
+        table::{ReadTableError, Table},
+        GenerateTableError, Tablebase, Wdl,
+    };
+
+    fn tablebase() -> &'static Tablebase {
+        static TABLEBASE: OnceLock<Tablebase> = OnceLock::new();
+
+        TABLEBASE.get_or_init(|| {
+            let mut tablebase = Tablebase::new();
+
+            for material in ["KQvK", "KRvK", "KPvK", "KBNvK"] {
+                tablebase.generate(&material.parse().unwrap()).unwrap();
+            }
+
+            tablebase
+        })
+    }
+
+    #[test]
+    fn pawns_on_both_sides() {
+        assert!(matches!(
+            Tablebase::new().generate(&"KPvKP".parse().unwrap()),
+            Err(GenerateTableError::PawnsOnBothSides)
+        ));
+    }
+
+    #[test]
+    fn too_many_pieces() {
+        assert!(matches!(
+            Tablebase::new().generate(&"KQRvKR".parse().unwrap()),
+            Err(GenerateTableError::TooManyPieces)
+        ));
+    }
+
+    #[test_case("KQvK", 19)]
+    #[test_case("KRvK", 31)]
+    #[test_case("KBNvK", 65)]
+    #[test_case("KBvK", 0; "bishop draw")]
+    #[test_case("KNvK", 0; "knight draw")]
+    fn longest_mates(material: &str, plies: u8) {
+        let table = tablebase().table(&material.parse().unwrap()).unwrap();
+
+        assert_eq!(table.longest_mate().unwrap_or(0), plies);
+    }
+
+    #[test_case("k7/8/1K6/8/8/8/7Q/8 w - - 0 1", Wdl::Win, Some(1); "mate in one")]
+    #[test_case("k7/8/1K6/8/8/8/7Q/8 b - - 0 1", Wdl::Draw, None; "queen stalemate")]
+    #[test_case("k7/8/1K6/8/8/8/8/6Q1 b - - 0 1", Wdl::Loss, Some(2); "queen loss")]
+    #[test_case("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1", Wdl::Draw, None; "stalemate")]
+    #[test_case("7k/8/8/8/8/8/7P/7K w - - 0 1", Wdl::Draw, None; "rook pawn")]
+    #[test_case("8/3KP2k/8/8/8/8/8/8 w - - 0 1", Wdl::Win, Some(11); "promotion")]
+    #[test_case("8/8/8/8/8/8/3kp2K/8 b - - 0 1", Wdl::Win, Some(11); "flipped promotion")]
+    #[test_case("8/8/8/8/8/8/3kp2K/8 w - - 0 1", Wdl::Loss, None; "flipped loss")]
+    #[test_case("7k/8/8/8/8/8/1K6/7r w - - 0 1", Wdl::Loss, None; "black rook")]
+    #[test_case("8/8/8/3k4/8/8/8/KBN5 w - - 0 1", Wdl::Win, None; "bishop and knight")]
+    fn probes(fen: &str, wdl: Wdl, plies_to_mate: Option<u8>) {
+        let board = fen.parse::<Board>().unwrap();
+        let probe = tablebase().probe(&board).unwrap();
+
+        assert_eq!(probe.wdl, wdl);
+
+        if plies_to_mate.is_some() {
+            assert_eq!(probe.plies_to_mate, plies_to_mate);
+        }
+
+        assert_eq!(probe.best_move.is_none(), mg::gen_moves(&board).is_empty());
+    }
+
+    // Checks that the value of sampled positions is the best among the values of their moves.
+    #[test_case("KQvK")]
+    #[test_case("KRvK")]
+    #[test_case("KPvK")]
+    #[test_case("KBNvK")]
+    fn consistent_values(material: &str) {
+        let tablebase = tablebase();
+        let indexer = Indexer::new(&material.parse().unwrap());
+
+        for index in (0..indexer.size()).step_by(97) {
+            let position = indexer.position(index);
+
+            if indexer.index(&position) != index {
+                continue;
+            }
+
+            let Some(board) = indexer.board(&position) else {
+                continue;
+            };
+
+            let probe = tablebase.probe(&board).unwrap();
+
+            let Some(best_move) = probe.best_move else {
+                let expected = board.in_check().then_some(0);
+                assert_eq!(probe.plies_to_mate, expected, "{board}");
+                continue;
+            };
+
+            let mut child = board;
+            child.make_move(best_move).unwrap();
+            let child_probe = tablebase.probe(&child).unwrap();
+
+            let expected_wdl = match child_probe.wdl {
+                Wdl::Win => Wdl::Loss,
+                Wdl::Draw => Wdl::Draw,
+                Wdl::Loss => Wdl::Win,
+            };
+
+            assert_eq!(probe.wdl, expected_wdl, "{board}");
+            assert_eq!(
+                probe.plies_to_mate,
+                child_probe.plies_to_mate.map(|plies| plies + 1),
+                "{board}"
+            );
+        }
+    }
+
+    #[test]
+    fn table_files() {
+        let table = tablebase().table(&"KRvK".parse().unwrap()).unwrap();
+
+        let mut bytes = vec![];
+        table.write(&mut bytes).unwrap();
+
+        assert_eq!(&Table::read(bytes.as_slice()).unwrap(), table);
+
+        assert!(matches!(
+            Table::read(&bytes[..bytes.len() - 1]),
+            Err(ReadTableError::Io(_))
+        ));
+
+        bytes[0] = b'X';
+        assert!(matches!(
+            Table::read(bytes.as_slice()),
+            Err(ReadTableError::InvalidMagic)
+        ));
+
+        let mut bytes = b"MGTB\x02\x06KQRvKR".to_vec();
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(
+            Table::read(bytes.as_slice()),
+            Err(ReadTableError::TooManyPieces)
+        ));
+    }
+
+    #[test_case("8/8/8/3k4/8/8/3K4/8 w - - 0 1", Some(WdlScore::Draw); "kings")]
+    #[test_case("8/8/8/3k4/8/8/3K4/Q7 w - - 0 1", None; "missing table")]
+    #[test_case("r3k3/8/8/8/8/8/8/4K3 w q - 0 1", None; "castling")]
+    fn syzygy_without_tables(fen: &str, expected: Option<WdlScore>) {
+        let syzygy = Syzygy::default();
+        let board = fen.parse::<Board>().unwrap();
+
+        assert_eq!(syzygy.probe_wdl(&board), expected);
+        assert_eq!(syzygy.probe_dtz(&board).is_some(), expected.is_some());
+        assert_eq!(syzygy.probe_root(&board).is_some(), expected.is_some());
+    }
+
+    #[test]
+    fn invalid_syzygy_files() {
+        let directory = env::temp_dir().join("mangrove-invalid-syzygy");
+        fs::create_dir_all(&directory).unwrap();
+
+        // The header says the table has pawns, which KQvK doesn't.
+        fs::write(directory.join("KQvK.rtbw"), [0x71, 0xe8, 0x23, 0x5d, 0x02]).unwrap();
+        fs::write(directory.join("KRvK.rtbw"), b"not a table").unwrap();
+        fs::write(directory.join("KRvK.txt"), b"").unwrap();
+        fs::write(directory.join("KKvK.rtbw"), b"").unwrap();
+
+        let syzygy = Syzygy::open(&directory).unwrap();
+        assert_eq!(syzygy.max_pieces(), 3);
+
+        for fen in [
+            "8/8/8/3k4/8/8/3K4/Q7 w - - 0 1",
+            "8/8/8/3k4/8/8/3K4/7r b - - 0 1",
+        ] {
+            assert_eq!(syzygy.probe_wdl(&fen.parse().unwrap()), None, "{fen}");
+        }
+    }
+
+    // The tests probing the official tables are ignored, as the tables aren't committed. Running
+    // them needs the variable to point to a directory with the 3-piece tables.
+    const SYZYGY_PATH_VAR: &str = "MANGROVE_SYZYGY_PATH";
+
+    fn syzygy() -> Syzygy {
+        let directory = env::var(SYZYGY_PATH_VAR)
+            .unwrap_or_else(|_| panic!("{SYZYGY_PATH_VAR} should point to the Syzygy tables"));
+        let syzygy = Syzygy::open(directory).unwrap();
+
+        assert_eq!(syzygy.max_pieces(), 3);
+        syzygy
+    }
+
+    // Tables may store the plies of some positions in moves, so the plies are checked up to one
+    // more than expected, as described in `Syzygy::probe_dtz`.
+    fn assert_dtz(dtz: Option<i32>, expected: i32, board: &Board) {
+        let dtz = dtz.unwrap();
+        let slack = if expected == 0 { 0 } else { 1 };
+
+        assert_eq!(dtz.signum(), expected.signum(), "{board}");
+        assert!(
+            (0..=slack).contains(&(dtz.abs() - expected.abs())),
+            "{dtz} {board}"
+        );
+    }
+
+    #[test_case("k7/8/1K6/8/8/8/7Q/8 w - - 0 1", WdlScore::Win, 1; "mate in one")]
+    #[test_case("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", WdlScore::Loss, -1; "mated")]
+    #[test_case("k7/8/8/8/8/8/8/K6R w - - 0 1", WdlScore::Win, 17; "rook")]
+    #[test_case("7k/8/8/8/8/8/1K6/7r w - - 0 1", WdlScore::Loss, -28; "black rook")]
+    #[test_case("8/3KP2k/8/8/8/8/8/8 w - - 0 1", WdlScore::Win, 1; "promotion")]
+    #[test_case("8/8/8/8/8/8/3kp2K/8 b - - 0 1", WdlScore::Win, 1; "flipped promotion")]
+    #[test_case("7k/8/8/8/8/8/7P/7K w - - 0 1", WdlScore::Draw, 0; "rook pawn")]
+    #[test_case("8/8/8/3k4/8/8/3K4/2B5 w - - 0 1", WdlScore::Draw, 0; "bishop")]
+    #[test_case("8/8/8/3k4/8/8/3K4/2n5 b - - 0 1", WdlScore::Draw, 0; "knight")]
+    #[ignore = "needs the official Syzygy tables"]
+    fn syzygy_probes(fen: &str, wdl: WdlScore, dtz: i32) {
+        let syzygy = syzygy();
+        let board = fen.parse::<Board>().unwrap();
+
+        assert_eq!(syzygy.probe_wdl(&board), Some(wdl));
+        assert_dtz(syzygy.probe_dtz(&board), dtz, &board);
+    }
+
+    // The WDL probes don't know the plies played since the last capture or pawn move, which only
+    // the root probes take into account.
+    #[test_case("k7/8/8/8/8/8/8/K6R w - - 80 60", WdlScore::Win, WdlScore::Win; "win")]
+    #[test_case("k7/8/8/8/8/8/8/K6R w - - 90 60", WdlScore::Win, WdlScore::CursedWin; "cursed win")]
+    #[test_case("7k/8/8/8/8/8/1K6/7r w - - 60 60", WdlScore::Loss, WdlScore::Loss; "loss")]
+    #[test_case(
+        "7k/8/8/8/8/8/1K6/7r w - - 80 60",
+        WdlScore::Loss,
+        WdlScore::BlessedLoss;
+        "blessed loss"
+    )]
+    #[ignore = "needs the official Syzygy tables"]
+    fn syzygy_clock(fen: &str, wdl: WdlScore, root_wdl: WdlScore) {
+        let syzygy = syzygy();
+        let board = fen.parse::<Board>().unwrap();
+        let root = syzygy.probe_root(&board).unwrap();
+
+        assert_eq!(syzygy.probe_wdl(&board), Some(wdl));
+        assert_eq!(root.wdl, root_wdl);
+        assert_eq!(Some(root.dtz), syzygy.probe_dtz(&board));
+    }
+
+    // Checks the results of the tables against the tablebase on sampled positions. Without pawns,
+    // mates are the only moves zeroing the clock, so the plies until zeroing are the plies until
+    // mate.
+    #[test_case("KQvK")]
+    #[test_case("KRvK")]
+    #[test_case("KPvK")]
+    #[ignore = "needs the official Syzygy tables"]
+    fn syzygy_consistent_values(material: &str) {
+        let syzygy = syzygy();
+        let tablebase = tablebase();
+        let material = material.parse::<MaterialSignature>().unwrap();
+        let indexer = Indexer::new(&material);
+
+        for index in (0..indexer.size()).step_by(7) {
+            let position = indexer.position(index);
+
+            if indexer.index(&position) != index {
+                continue;
+            }
+
+            let Some(board) = indexer.board(&position) else {
+                continue;
+            };
+
+            let probe = tablebase.probe(&board).unwrap();
+            let wdl = match probe.wdl {
+                Wdl::Win => WdlScore::Win,
+                Wdl::Draw => WdlScore::Draw,
+                Wdl::Loss => WdlScore::Loss,
+            };
+
+            assert_eq!(syzygy.probe_wdl(&board), Some(wdl), "{board}");
+
+            if !material.has_pawns() {
+                let plies = probe.plies_to_mate.map_or(0, |plies| plies.max(1) as i32);
+                let expected = match wdl {
+                    WdlScore::Loss => -plies,
+                    _ => plies,
+                };
+
+                assert_dtz(syzygy.probe_dtz(&board), expected, &board);
+            }
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use arrayvec::ArrayVec;
+use mangrove_bootstrap::{BitBoard, Color, Square};
+use mangrove_core::{
+    board::Board,
+    repr::{CastlingMode, PieceKind, Player},
+};
+
+use mangrove_core::material::MaterialSignature;
+
+use crate::MAX_PIECES;
+
+/// The maximum number of men in a tablebase position, including the kings.
+pub(crate) const MAX_MEN: usize = MAX_PIECES + 2;
+
+/// A position of a table, as the squares of the white king, the black king, and the other pieces
+/// in the order of the material, along with the side to move. Squares past the men of the
+/// material are unused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Position {
+    pub squares: [u8; MAX_MEN],
+    pub color: Color,
+}
+
+impl Position {
+    /// Returns the position of a board with the passed material.
+    pub fn from_board(board: &Board, material: &MaterialSignature) -> Position {
+        let square = |bitboard: BitBoard| bitboard.first_one_as_square().unwrap().as_index() as u8;
+
+        let mut squares = [0; MAX_MEN];
+        squares[0] = square(board.player(Color::White).king);
+        squares[1] = square(board.player(Color::Black).king);
+
+        let mut remaining = [Color::White, Color::Black].map(|color| *board.player(color));
+
+        for (index, (color, kind)) in material.pieces().enumerate() {
+            let player = &mut remaining[color as usize];
+            let piece_square = player.piece_bitboard(kind).first_one_as_square().unwrap();
+
+            player.toggle_piece(piece_square, kind);
+            squares[index + 2] = piece_square.as_index() as u8;
+        }
+
+        Position {
+            squares,
+            color: board.playing_color,
+        }
+    }
+
+    /// Returns the position after moving the man on the origin to the empty target square.
+    pub fn with_move(&self, origin: u8, target: u8) -> Position {
+        let mut position = Position {
+            color: !self.color,
+            ..*self
+        };
+
+        for square in &mut position.squares {
+            if *square == origin {
+                *square = target;
+                break;
+            }
+        }
+
+        position
+    }
+}
+
+// A symmetry of the board, as mirroring the files, mirroring the ranks, and then swapping files
+// with ranks, in that order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Symmetry {
+    mirror_files: bool,
+    mirror_ranks: bool,
+    transpose: bool,
+}
+
+impl Symmetry {
+    const fn new(mirror_files: bool, mirror_ranks: bool, transpose: bool) -> Self {
+        Self {
+            mirror_files,
+            mirror_ranks,
+            transpose,
+        }
+    }
+
+    fn apply(self, square: u8) -> u8 {
+        let mut file = square & 7;
+        let mut rank = square >> 3;
+
+        if self.mirror_files {
+            file ^= 7;
+        }
+
+        if self.mirror_ranks {
+            rank ^= 7;
+        }
+
+        if self.transpose {
+            (file, rank) = (rank, file);
+        }
+
+        rank << 3 | file
+    }
+}
+
+// Pawns only allow mirroring the files, as they can't move backwards.
+const PAWN_SYMMETRIES: [Symmetry; 2] = [
+    Symmetry::new(false, false, false),
+    Symmetry::new(true, false, false),
+];
+
+const ALL_SYMMETRIES: [Symmetry; 8] = [
+    Symmetry::new(false, false, false),
+    Symmetry::new(true, false, false),
+    Symmetry::new(false, true, false),
+    Symmetry::new(true, true, false),
+    Symmetry::new(false, false, true),
+    Symmetry::new(true, false, true),
+    Symmetry::new(false, true, true),
+    Symmetry::new(true, true, true),
+];
+
+/// Maps the positions of a material to the indices of its table and back. Positions are reduced
+/// by the symmetries of the board, so that the white king is on the A to D files with pawns, and
+/// in the A1-D1-D4 triangle without them. Each position has a single canonical index, which is
+/// the smallest among the ones of its symmetric positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Indexer {
+    pieces: ArrayVec<(Color, PieceKind), MAX_PIECES>,
+    symmetries: &'static [Symmetry],
+    king_indices: [Option<u8>; 64],
+    king_squares: ArrayVec<u8, 32>,
+}
+
+impl Indexer {
+    pub fn new(material: &MaterialSignature) -> Self {
+        let has_pawns = material.has_pawns();
+
+        let king_squares = (0..64)
+            .filter(|&square: &u8| {
+                let (file, rank) = (square & 7, square >> 3);
+                file <= 3 && (has_pawns || rank <= file)
+            })
+            .collect::<ArrayVec<_, 32>>();
+
+        let mut king_indices = [None; 64];
+        for (index, &square) in king_squares.iter().enumerate() {
+            king_indices[square as usize] = Some(index as u8);
+        }
+
+        Self {
+            pieces: material.pieces().collect(),
+            symmetries: if has_pawns {
+                &PAWN_SYMMETRIES
+            } else {
+                &ALL_SYMMETRIES
+            },
+            king_indices,
+            king_squares,
+        }
+    }
+
+    /// Returns the number of men of the material, including the kings.
+    pub fn men(&self) -> usize {
+        self.pieces.len() + 2
+    }
+
+    /// Returns the color and kind of the man at the passed index of a position.
+    pub fn man(&self, index: usize) -> (Color, PieceKind) {
+        match index {
+            0 => (Color::White, PieceKind::King),
+            1 => (Color::Black, PieceKind::King),
+            _ => self.pieces[index - 2],
+        }
+    }
+
+    /// Returns the number of indices of the table, some of which aren't valid positions.
+    pub fn size(&self) -> usize {
+        2 * self.king_squares.len() * 64usize.pow(self.men() as u32 - 1)
+    }
+
+    // Returns the index of the position without applying symmetries, or `None` if the white king
+    // is out of its reduced area.
+    fn raw_index(&self, position: &Position) -> Option<usize> {
+        let king_index = self.king_indices[position.squares[0] as usize]?;
+
+        Some(position.squares[1..self.men()].iter().fold(
+            position.color as usize * self.king_squares.len() + king_index as usize,
+            |index, &square| index * 64 + square as usize,
+        ))
+    }
+
+    /// Returns the canonical index of the position.
+    pub fn index(&self, position: &Position) -> usize {
+        self.symmetries
+            .iter()
+            .filter_map(|&symmetry| {
+                let mut transformed = *position;
+
+                for square in &mut transformed.squares[..self.men()] {
+                    *square = symmetry.apply(*square);
+                }
+
+                // Identical pieces are sorted by square, so that swapping them doesn't change the
+                // index.
+                for index in 2..self.men() - 1 {
+                    if self.man(index) == self.man(index + 1)
+                        && transformed.squares[index] > transformed.squares[index + 1]
+                    {
+                        transformed.squares.swap(index, index + 1);
+                    }
+                }
+
+                self.raw_index(&transformed)
+            })
+            .min()
+            .unwrap()
+    }
+
+    /// Returns the position at the passed index, which may not be valid.
+    pub fn position(&self, mut index: usize) -> Position {
+        let mut squares = [0; MAX_MEN];
+
+        for square in squares[1..self.men()].iter_mut().rev() {
+            *square = (index % 64) as u8;
+            index /= 64;
+        }
+
+        squares[0] = self.king_squares[index % self.king_squares.len()];
+
+        Position {
+            squares,
+            color: if index < self.king_squares.len() {
+                Color::White
+            } else {
+                Color::Black
+            },
+        }
+    }
+
+    /// Returns the board of the position, or `None` if it isn't a legal position.
+    pub fn board(&self, position: &Position) -> Option<Board> {
+        let mut players = [Player::blank(), Player::blank()];
+
+        for (index, &square) in position.squares[..self.men()].iter().enumerate() {
+            let (color, kind) = self.man(index);
+            let square = Square::try_from(square).unwrap();
+
+            if (players[0].occupation | players[1].occupation).get_bit(square)
+                || kind == PieceKind::Pawn && BitBoard::EDGE_RANKS.get_bit(square)
+            {
+                return None;
+            }
+
+            players[color as usize].toggle_piece(square, kind);
+        }
+
+        let [white, black] = players;
+
+        Board::from_players(
+            white,
+            black,
+            position.color,
+            None,
+            0,
+            1,
+            CastlingMode::Standard,
+        )
+        .ok()
+    }
+
+    /// Returns the canonical indices of the positions the side not to move could have come from
+    /// with a move that keeps the material, without checking if they are legal.
+    pub fn predecessors(&self, position: &Position, predecessors: &mut Vec<u32>) {
+        let mover = !position.color;
+        let occupied = position.squares[..self.men()]
+            .iter()
+            .fold(0u64, |occupied, &square| occupied | 1 << square);
+
+        predecessors.clear();
+
+        for index in 0..self.men() {
+            let (color, kind) = self.man(index);
+
+            if color != mover {
+                continue;
+            }
+
+            let target = position.squares[index];
+            let origins = match kind {
+                PieceKind::Pawn => pawn_origins(target, mover, occupied),
+                _ => reach(kind, target, occupied) & !occupied,
+            };
+
+            for origin in (0..64).filter(|&origin| origins & 1 << origin != 0) {
+                let mut predecessor = *position;
+                predecessor.squares[index] = origin;
+                predecessor.color = mover;
+
+                predecessors.push(self.index(&predecessor) as u32);
+            }
+        }
+
+        predecessors.sort_unstable();
+        predecessors.dedup();
+    }
+}
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+// Returns the square one step away in the passed direction, if it's on the board.
+fn step(square: u8, (file_step, rank_step): (i8, i8)) -> Option<u8> {
+    let file = (square & 7) as i8 + file_step;
+    let rank = (square >> 3) as i8 + rank_step;
+
+    ((0..8).contains(&file) && (0..8).contains(&rank)).then_some((rank << 3 | file) as u8)
+}
+
+// Returns the squares a piece other than a pawn attacks from the passed square. The moves of
+// these pieces are reversible, so these are also the squares it could have come from.
+fn reach(kind: PieceKind, square: u8, occupied: u64) -> u64 {
+    let (steps, slides): (&[(i8, i8)], bool) = match kind {
+        PieceKind::King => (&KING_STEPS, false),
+        PieceKind::Knight => (&KNIGHT_STEPS, false),
+        PieceKind::Rook => (&ROOK_DIRECTIONS, true),
+        PieceKind::Bishop => (&BISHOP_DIRECTIONS, true),
+        PieceKind::Queen => (&KING_STEPS, true),
+        PieceKind::Pawn => unreachable!("pawn moves aren't reversible"),
+    };
+
+    let mut reach = 0;
+
+    for &direction in steps {
+        let mut current = square;
+
+        while let Some(next) = step(current, direction) {
+            reach |= 1 << next;
+
+            if !slides || occupied & 1 << next != 0 {
+                break;
+            }
+
+            current = next;
+        }
+    }
+
+    reach
+}
+
+// Returns the empty squares a pawn of the passed color could have been pushed from.
+fn pawn_origins(square: u8, color: Color, occupied: u64) -> u64 {
+    let (back, fourth_rank) = match color {
+        Color::White => ((0, -1), 3),
+        Color::Black => ((0, 1), 4),
+    };
+
+    let Some(single) = step(square, back) else {
+        return 0;
+    };
+
+    if occupied & 1 << single != 0 || matches!(single >> 3, 0 | 7) {
+        return 0;
+    }
+
+    let mut origins = 1 << single;
+
+    if square >> 3 == fourth_rank {
+        let double = step(single, back).unwrap();
+
+        if occupied & 1 << double == 0 {
+            origins |= 1 << double;
+        }
+    }
+
+    origins
+}
@@ -0,0 +1,532 @@
+//! Probing of Syzygy tablebases, which store the result of every position with up to 7 pieces
+//! under the fifty-move rule (WDL files), and the number of plies until the next capture or pawn
+//! move with best play (DTZ files).
+//!
+//! Tables don't store positions where en passant is possible, or the positions which are better
+//! solved by a capture or a pawn move, so probing searches these moves before reading the tables.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io,
+    ops::Neg,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use arrayvec::ArrayVec;
+use mangrove_bootstrap::Color;
+use mangrove_core::{
+    board::Board,
+    material::MaterialSignature,
+    mg,
+    repr::{CastlingSide, ChessMove, PieceKind},
+};
+
+use self::table::{leading_pawn_file, Table, TableInfo, TableKind, MAX_PIECES};
+
+mod table;
+
+const WDL_EXTENSION: &str = "rtbw";
+const DTZ_EXTENSION: &str = "rtbz";
+
+/// The pieces along with the type encoded in the tables.
+const PIECES: [(PieceKind, u8); 6] = [
+    (PieceKind::King, 6),
+    (PieceKind::Queen, 5),
+    (PieceKind::Rook, 4),
+    (PieceKind::Bishop, 3),
+    (PieceKind::Knight, 2),
+    (PieceKind::Pawn, 1),
+];
+
+/// The rank of the moves winning without being drawn by the fifty-move rule.
+const MAX_DTZ: i32 = 1 << 18;
+
+/// The result of a position with best play under the fifty-move rule, from the view of the side
+/// to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WdlScore {
+    Loss,
+    /// A loss which the fifty-move rule turns into a draw.
+    BlessedLoss,
+    Draw,
+    /// A win which the fifty-move rule turns into a draw.
+    CursedWin,
+    Win,
+}
+
+impl WdlScore {
+    fn from_value(value: i32) -> Option<Self> {
+        Some(match value {
+            -2 => Self::Loss,
+            -1 => Self::BlessedLoss,
+            0 => Self::Draw,
+            1 => Self::CursedWin,
+            2 => Self::Win,
+            _ => return None,
+        })
+    }
+
+    fn value(self) -> i8 {
+        self as i8 - 2
+    }
+
+    // Returns the number of plies until the next capture or pawn move of a position which is
+    // about to make one.
+    fn dtz_before_zeroing(self) -> i32 {
+        match self {
+            Self::Loss => -1,
+            Self::BlessedLoss => -101,
+            Self::Draw => 0,
+            Self::CursedWin => 101,
+            Self::Win => 1,
+        }
+    }
+}
+
+impl Neg for WdlScore {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::from_value(-self.value() as i32).unwrap()
+    }
+}
+
+/// The result of probing the root of a search with [`Syzygy::probe_root`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootProbe {
+    /// The result of the board, drawing the wins and losses which the fifty-move rule can reach
+    /// given the plies already played since the last capture or pawn move.
+    pub wdl: WdlScore,
+    /// The number of plies until the next capture or pawn move, as returned by
+    /// [`Syzygy::probe_dtz`].
+    pub dtz: i32,
+    /// The move that keeps the best result under the fifty-move rule, or `None` if there are no
+    /// legal moves.
+    pub best_move: Option<ChessMove>,
+}
+
+// The value read from a table.
+enum TableValue {
+    Value(i32),
+    // DTZ tables only store one side to move for most materials.
+    OtherSide,
+}
+
+/// The files of a table, which are opened the first time they are probed.
+#[derive(Debug)]
+struct Entry {
+    material: MaterialSignature,
+    info: TableInfo,
+    paths: [Option<PathBuf>; 2],
+    tables: [OnceLock<Option<Table>>; 2],
+}
+
+impl Entry {
+    fn table(&self, kind: TableKind) -> Option<&Table> {
+        let index = kind as usize;
+
+        self.tables[index]
+            .get_or_init(|| {
+                let file = File::open(self.paths[index].as_ref()?).ok()?;
+                Table::new(file, kind, self.info.clone())
+            })
+            .as_ref()
+    }
+}
+
+/// A directory of Syzygy tables. Positions with the kings alone are always drawn, and positions
+/// where either side can still castle are never probed.
+#[derive(Debug, Default)]
+pub struct Syzygy {
+    entries: Vec<Entry>,
+    // The index of the entry of each table material, with either color first.
+    materials: HashMap<MaterialSignature, usize>,
+    max_pieces: usize,
+}
+
+// Returns the number of pieces of each type of each color, indexed by the type in the tables.
+fn piece_counts(material: &MaterialSignature) -> [[usize; 7]; 2] {
+    [Color::White, Color::Black].map(|color| {
+        let mut counts = [0; 7];
+        counts[6] = 1;
+
+        for &kind in material.side(color) {
+            let &(_, code) = PIECES.iter().find(|&&(other, _)| other == kind).unwrap();
+            counts[code as usize] += 1;
+        }
+
+        counts
+    })
+}
+
+fn is_capture(board: &Board, chess_move: ChessMove) -> bool {
+    board.them.occupation.get_bit(chess_move.target)
+        || board.us.pawns.get_bit(chess_move.origin)
+            && board.en_passant_capture_square == Some(chess_move.target)
+}
+
+fn is_zeroing(board: &Board, chess_move: ChessMove) -> bool {
+    board.us.pawns.get_bit(chess_move.origin) || is_capture(board, chess_move)
+}
+
+fn is_mate(board: &Board) -> bool {
+    board.in_check() && mg::gen_moves(board).is_empty()
+}
+
+impl Syzygy {
+    /// Opens the tables of the directory, without reading them until they are probed. Files other
+    /// than WDL (`.rtbw`) and DTZ (`.rtbz`) tables are ignored.
+    pub fn open(directory: impl AsRef<Path>) -> io::Result<Self> {
+        let mut syzygy = Self::default();
+
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+
+            let kind = match path.extension().and_then(|extension| extension.to_str()) {
+                Some(WDL_EXTENSION) => TableKind::Wdl,
+                Some(DTZ_EXTENSION) => TableKind::Dtz,
+                _ => continue,
+            };
+
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let Ok(material) = name.parse::<MaterialSignature>() else {
+                continue;
+            };
+
+            if material.piece_count() + 2 > MAX_PIECES {
+                continue;
+            }
+
+            let index = match syzygy.materials.get(&material) {
+                Some(&index) => index,
+                None => {
+                    let index = syzygy.entries.len();
+
+                    syzygy.materials.insert(material.clone(), index);
+                    syzygy.materials.insert(material.flipped(), index);
+                    syzygy.entries.push(Entry {
+                        info: TableInfo::new(piece_counts(&material)),
+                        material,
+                        paths: Default::default(),
+                        tables: Default::default(),
+                    });
+
+                    index
+                }
+            };
+
+            let entry = &mut syzygy.entries[index];
+            entry.paths[kind as usize] = Some(path);
+            syzygy.max_pieces = syzygy.max_pieces.max(entry.info.piece_count);
+        }
+
+        Ok(syzygy)
+    }
+
+    /// Returns the largest number of pieces of the tables, including the kings.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    // Checks if the board can be probed, which requires no castling rights and few enough pieces.
+    fn can_probe(&self, board: &Board) -> bool {
+        let pieces = (board.us.occupation | board.them.occupation).count_ones() as usize;
+
+        pieces <= self.max_pieces.max(2)
+            && [&board.us, &board.them].into_iter().all(|player| {
+                CastlingSide::ALL
+                    .into_iter()
+                    .all(|side| player.castling_rook(side).is_none())
+            })
+    }
+
+    // Reads the value of the board in a table, ignoring captures and en passant.
+    fn probe_table(&self, board: &Board, kind: TableKind, wdl: WdlScore) -> Option<TableValue> {
+        let material = MaterialSignature::of_board(board);
+        let entry = &self.entries[*self.materials.get(&material)?];
+        let table = entry.table(kind)?;
+
+        // Tables are stored with the pieces of the first side of their name as white, and
+        // symmetric tables only with white to move, so colors are flipped to match them.
+        let flip = material != entry.material
+            || entry.info.symmetric && board.playing_color == Color::Black;
+        let side = (flip != (board.playing_color == Color::Black)) as usize;
+        let (flip_squares, flip_pieces) = if flip { (56, 8) } else { (0, 0) };
+
+        let mut squares = ArrayVec::<u8, MAX_PIECES>::new();
+        let mut pieces = ArrayVec::<u8, MAX_PIECES>::new();
+        let mut file = 0;
+
+        // The pawns of the color with the fewest of them lead the encoding, with their file
+        // selecting the part of the table.
+        let lead_color = entry.info.has_pawns.then(|| {
+            let lead = table.pieces(0, 0)[0];
+            let color = if lead ^ flip_pieces < 8 {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            for square in board.player(color).pawns.bits() {
+                squares.push(square.as_index() as u8 ^ flip_squares);
+                pieces.push(lead);
+            }
+
+            file = leading_pawn_file(&mut squares);
+            color
+        });
+
+        if !table.stores_side(side, file) {
+            return Some(TableValue::OtherSide);
+        }
+
+        let lead_count = squares.len();
+
+        for color in [Color::White, Color::Black] {
+            let color_code = if color == Color::Black { 8 } else { 0 };
+
+            for (kind, code) in PIECES {
+                if kind == PieceKind::Pawn && lead_color == Some(color) {
+                    continue;
+                }
+
+                for square in board.player(color).piece_bitboard(kind).bits() {
+                    squares.push(square.as_index() as u8 ^ flip_squares);
+                    pieces.push((code | color_code) ^ flip_pieces);
+                }
+            }
+        }
+
+        // The pieces are ordered as the table encodes them.
+        let order = table.pieces(side, file);
+
+        for (index, &piece) in order.iter().enumerate().skip(lead_count) {
+            if let Some(other) = (index..pieces.len()).find(|&other| pieces[other] == piece) {
+                pieces.swap(index, other);
+                squares.swap(index, other);
+            }
+        }
+
+        table
+            .probe(side, file, &mut squares, wdl.value())
+            .map(TableValue::Value)
+    }
+
+    fn probe_wdl_table(&self, board: &Board) -> Option<WdlScore> {
+        if (board.us.occupation | board.them.occupation).count_ones() == 2 {
+            return Some(WdlScore::Draw);
+        }
+
+        match self.probe_table(board, TableKind::Wdl, WdlScore::Draw)? {
+            TableValue::Value(value) => WdlScore::from_value(value),
+            TableValue::OtherSide => None,
+        }
+    }
+
+    // Returns the result of the board by searching captures, and pawn moves if asked, before
+    // probing the WDL tables, along with whether the best move is one of the searched moves.
+    fn search(&self, board: &Board, pawn_moves: bool) -> Option<(WdlScore, bool)> {
+        let moves = mg::gen_moves(board);
+        let mut best = WdlScore::Loss;
+        let mut searched = 0;
+
+        for &chess_move in &moves {
+            if !(is_capture(board, chess_move)
+                || pawn_moves && board.us.pawns.get_bit(chess_move.origin))
+            {
+                continue;
+            }
+
+            searched += 1;
+
+            let mut child = *board;
+            child.make_move(chess_move).unwrap();
+
+            let value = -self.search(&child, false)?.0;
+
+            if value > best {
+                best = value;
+
+                if value == WdlScore::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        // The table isn't probed when every move was searched, as it's wrong for positions with
+        // en passant, and may store any value for positions solved by a capture.
+        let searched_all = searched != 0 && searched == moves.len();
+        let value = if searched_all {
+            best
+        } else {
+            self.probe_wdl_table(board)?
+        };
+
+        Some(if best >= value {
+            (best, best > WdlScore::Draw || searched_all)
+        } else {
+            (value, false)
+        })
+    }
+
+    // Returns the number of plies until the next capture or pawn move, as described in
+    // `probe_dtz`.
+    fn dtz(&self, board: &Board) -> Option<i32> {
+        let (wdl, zeroing) = self.search(board, true)?;
+
+        if wdl == WdlScore::Draw {
+            return Some(0);
+        } else if zeroing {
+            return Some(wdl.dtz_before_zeroing());
+        }
+
+        let sign = wdl.value().signum() as i32;
+
+        match self.probe_table(board, TableKind::Dtz, wdl)? {
+            TableValue::Value(dtz) => {
+                let cursed = matches!(wdl, WdlScore::CursedWin | WdlScore::BlessedLoss);
+                Some((dtz + if cursed { 100 } else { 0 }) * sign)
+            }
+            // The table only stores the other side to move, so the best child is searched.
+            TableValue::OtherSide => {
+                let mut min_dtz = i32::MAX;
+
+                for chess_move in mg::gen_moves(board) {
+                    let zeroing = is_zeroing(board, chess_move);
+
+                    let mut child = *board;
+                    child.make_move(chess_move).unwrap();
+
+                    let mut dtz = if zeroing {
+                        -(-self.search(&child, false)?.0).dtz_before_zeroing()
+                    } else {
+                        -self.dtz(&child)?
+                    };
+
+                    if dtz == 1 && is_mate(&child) {
+                        min_dtz = 1;
+                    }
+
+                    if !zeroing {
+                        dtz += dtz.signum();
+                    }
+
+                    if dtz < min_dtz && dtz.signum() == sign {
+                        min_dtz = dtz;
+                    }
+                }
+
+                Some(if min_dtz == i32::MAX { -1 } else { min_dtz })
+            }
+        }
+    }
+
+    /// Probes the result of the board, or returns `None` if its tables are missing or if either
+    /// side can still castle.
+    ///
+    /// Only the WDL tables are read, so the plies already played since the last capture or pawn
+    /// move are not taken into account. This makes it cheap enough for the leaves of a search,
+    /// while [`Syzygy::probe_root`] also reads the DTZ tables.
+    pub fn probe_wdl(&self, board: &Board) -> Option<WdlScore> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        Some(self.search(board, false)?.0)
+    }
+
+    /// Probes the number of plies until the next capture or pawn move with best play, positive
+    /// when winning and negative when losing, or returns `None` if its tables are missing or if
+    /// either side can still castle.
+    ///
+    /// Drawn positions return `0`, and `100` is added to the number of plies of cursed wins and
+    /// blessed losses. As some tables store moves instead of plies, the value may be one more
+    /// than the actual number of plies.
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        self.dtz(board)
+    }
+
+    /// Probes the board along with each of its moves in the WDL and DTZ tables, which is meant
+    /// for the root of a search. Wins and losses which the fifty-move rule draws given the plies
+    /// already played since the last capture or pawn move are reported as cursed wins and blessed
+    /// losses.
+    ///
+    /// Among the moves keeping the best result under the fifty-move rule, the best move reaches
+    /// the next capture or pawn move the soonest when winning, and delays it the most when
+    /// losing. Repetitions are not taken into account.
+    ///
+    /// Returns `None` if tables are missing for the board or one of its children, or if either
+    /// side can still castle.
+    pub fn probe_root(&self, board: &Board) -> Option<RootProbe> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        let (wdl, _) = self.search(board, false)?;
+        let dtz = self.dtz(board)?;
+
+        let wdl = match wdl {
+            WdlScore::Win if dtz + board.min_ply_clock as i32 > 100 => WdlScore::CursedWin,
+            WdlScore::Loss if -dtz + board.min_ply_clock as i32 > 100 => WdlScore::BlessedLoss,
+            wdl => wdl,
+        };
+
+        Some(RootProbe {
+            wdl,
+            dtz,
+            best_move: self.best_move(board)?,
+        })
+    }
+
+    // Returns the best move of the board as described in `probe_root`, or `None` if tables are
+    // missing for one of its children.
+    fn best_move(&self, board: &Board) -> Option<Option<ChessMove>> {
+        let clock = board.min_ply_clock as i32;
+        let mut best = None::<(i32, i32, ChessMove)>;
+
+        for chess_move in mg::gen_moves(board) {
+            let mut child = *board;
+            child.make_move(chess_move).unwrap();
+
+            let mate = is_mate(&child);
+
+            let mut dtz = if child.min_ply_clock == 0 {
+                (-self.search(&child, false)?.0).dtz_before_zeroing()
+            } else if child.min_ply_clock >= 100 && !mate {
+                0
+            } else {
+                let dtz = -self.dtz(&child)?;
+                dtz + dtz.signum()
+            };
+
+            if mate && dtz == 2 {
+                dtz = 1;
+            }
+
+            // Wins and losses which the fifty-move rule can't reach are ranked equally, while the
+            // others are ranked by how soon the rule draws them.
+            let rank = match dtz {
+                1.. if dtz + clock <= 99 => MAX_DTZ,
+                1.. => MAX_DTZ - (dtz + clock),
+                ..0 if -dtz * 2 + clock < 100 => -MAX_DTZ,
+                ..0 => -MAX_DTZ + (-dtz + clock),
+                0 => 0,
+            };
+
+            if best.is_none_or(|(best_rank, best_dtz, _)| (rank, -dtz) > (best_rank, -best_dtz)) {
+                best = Some((rank, dtz, chess_move));
+            }
+        }
+
+        Some(best.map(|(_, _, chess_move)| chess_move))
+    }
+}
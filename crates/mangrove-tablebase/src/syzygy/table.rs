@@ -0,0 +1,913 @@
+use std::{fs::File, sync::OnceLock};
+
+/// The maximum number of pieces in a Syzygy table, including the kings.
+pub(crate) const MAX_PIECES: usize = 7;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+// The flags stored for each part of a table.
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+// The flags stored in the first byte of a table.
+const HEADER_SPLIT: u8 = 1;
+const HEADER_HAS_PAWNS: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+/// The lookup tables used to compute the index of a position, shared by all tables.
+struct Maps {
+    /// Maps the squares from A2 to H7 to `0..48`, such that the leading pawn, which is nearest
+    /// to the edge and then on the lowest rank, has the highest value.
+    pawns: [u8; 64],
+    /// Maps the squares below the A1-H8 diagonal to `0..28`.
+    b1h1h7: [u8; 64],
+    /// Maps the squares of the A1-D1-D4 triangle to `0..10`, the diagonal ones last.
+    a1d1d4: [u8; 64],
+    /// Maps the 462 legal placements of two kings, the first in the A1-D1-D4 triangle and
+    /// indexed by its value in `a1d1d4`.
+    kk: [[u16; 64]; 10],
+    /// The binomial coefficients, as `binomial[k][n]` ways of choosing `k` of `n` elements.
+    binomial: [[u64; 64]; 6],
+    /// The index of the leading pawns group by the square of the leading pawn.
+    lead_pawn_index: [[u64; 64]; 6],
+    /// The number of placements of the leading pawns group by the file of the leading pawn.
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+// The offset of a square from the A1-H8 diagonal, negative below it.
+fn off_diagonal(square: u8) -> i8 {
+    (square >> 3) as i8 - (square & 7) as i8
+}
+
+fn maps() -> &'static Maps {
+    static MAPS: OnceLock<Maps> = OnceLock::new();
+
+    MAPS.get_or_init(|| {
+        let mut maps = Maps {
+            pawns: [0; 64],
+            b1h1h7: [0; 64],
+            a1d1d4: [0; 64],
+            kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            lead_pawn_index: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        for (code, square) in (0..64)
+            .filter(|&square| off_diagonal(square) < 0)
+            .enumerate()
+        {
+            maps.b1h1h7[square as usize] = code as u8;
+        }
+
+        let triangle = (0..=27).filter(|&square: &u8| square & 7 <= 3);
+        let below = triangle.clone().filter(|&square| off_diagonal(square) < 0);
+        let diagonal = triangle.filter(|&square| off_diagonal(square) == 0);
+
+        for (code, square) in below.chain(diagonal).enumerate() {
+            maps.a1d1d4[square as usize] = code as u8;
+        }
+
+        let mut code = 0;
+        let mut both_on_diagonal = vec![];
+
+        for index in 0..10 {
+            for first in (0..=27).filter(|&square| {
+                square & 7 <= 3
+                    && off_diagonal(square) <= 0
+                    && maps.a1d1d4[square as usize] as usize == index
+            }) {
+                for second in 0..64u8 {
+                    let adjacent = (first & 7).abs_diff(second & 7) <= 1
+                        && (first >> 3).abs_diff(second >> 3) <= 1;
+
+                    if adjacent || off_diagonal(first) == 0 && off_diagonal(second) > 0 {
+                        continue;
+                    } else if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                        both_on_diagonal.push((index, second));
+                    } else {
+                        maps.kk[index][second as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+
+        for (index, second) in both_on_diagonal {
+            maps.kk[index][second as usize] = code;
+            code += 1;
+        }
+
+        maps.binomial[0][0] = 1;
+
+        for n in 1..64 {
+            for k in 0..6.min(n + 1) {
+                maps.binomial[k][n] = if k > 0 {
+                    maps.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n { maps.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut available_squares = 47;
+
+        for lead_pawns in 1..=5 {
+            for file in 0..4 {
+                let mut index = 0;
+
+                for rank in 1..=6 {
+                    let square = rank << 3 | file;
+
+                    if lead_pawns == 1 {
+                        maps.pawns[square] = available_squares;
+                        maps.pawns[square ^ 7] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2);
+                    }
+
+                    maps.lead_pawn_index[lead_pawns][square] = index;
+                    index += maps.binomial[lead_pawns - 1][maps.pawns[square] as usize];
+                }
+
+                maps.lead_pawns_size[lead_pawns][file] = index;
+            }
+        }
+
+        maps
+    })
+}
+
+/// Moves the leading pawn, which is the one with the highest value in the pawn map, to the front
+/// of the squares of the leading pawns, and returns the distance of its file from the edge.
+pub(crate) fn leading_pawn_file(squares: &mut [u8]) -> usize {
+    let maps = maps();
+    let (leading, _) = squares
+        .iter()
+        .enumerate()
+        .max_by_key(|&(_, &square)| maps.pawns[square as usize])
+        .unwrap();
+
+    squares.swap(0, leading);
+
+    let file = (squares[0] & 7) as usize;
+    file.min(7 - file)
+}
+
+/// The material of a table, as needed to index its positions.
+#[derive(Debug, Clone)]
+pub(crate) struct TableInfo {
+    pub piece_count: usize,
+    pub has_pawns: bool,
+    pub has_unique_pieces: bool,
+    /// The number of pawns of the leading color, and of the other color.
+    pub pawn_counts: [usize; 2],
+    /// Whether both sides have the same pieces.
+    pub symmetric: bool,
+}
+
+impl TableInfo {
+    /// Returns the information of a table from the piece counts of each color, indexed by the
+    /// type of piece.
+    pub fn new(counts: [[usize; 7]; 2]) -> Self {
+        let [white_pawns, black_pawns] = counts.map(|counts| counts[1]);
+        let white_leads = black_pawns == 0 || white_pawns != 0 && black_pawns >= white_pawns;
+
+        Self {
+            piece_count: counts.iter().flatten().sum(),
+            has_pawns: white_pawns + black_pawns != 0,
+            has_unique_pieces: counts.iter().any(|counts| counts[1..6].contains(&1)),
+            pawn_counts: if white_leads {
+                [white_pawns, black_pawns]
+            } else {
+                [black_pawns, white_pawns]
+            },
+            symmetric: counts[0] == counts[1],
+        }
+    }
+}
+
+/// The indexing and compression information of a part of a table, for one side to move and one
+/// file of the leading pawn.
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    block_size: usize,
+    span: usize,
+    num_blocks: usize,
+    min_sym_len: u8,
+    /// The offset of the lowest symbol of each length.
+    lowest_sym: usize,
+    /// The offset of the pairs of symbols each symbol expands to.
+    btree: usize,
+    /// The offset of the number of values minus one stored in each block.
+    block_lengths: usize,
+    block_lengths_size: usize,
+    /// The offset of the entries pointing into the block lengths every `span` values.
+    sparse_index: usize,
+    sparse_index_size: usize,
+    /// The offset of the compressed blocks.
+    data: usize,
+    /// The lowest symbol of each length, left-aligned in 64 bits.
+    base64: Vec<u64>,
+    /// The number of values minus one represented by each symbol.
+    symlen: Vec<u8>,
+    pieces: [u8; MAX_PIECES],
+    group_index: [u64; MAX_PIECES + 1],
+    group_len: [usize; MAX_PIECES + 1],
+    /// The offsets of the DTZ values of wins, losses, cursed wins and blessed losses in the map.
+    map_index: [usize; 4],
+}
+
+/// A Syzygy WDL or DTZ file. Only its headers are kept in memory, up to the sparse indices,
+/// while the entries of these, the block lengths and the compressed blocks are read from the file
+/// when probing.
+#[derive(Debug)]
+pub(crate) struct Table {
+    file: File,
+    header: Vec<u8>,
+    kind: TableKind,
+    info: TableInfo,
+    /// The parts of the table, by side to move and by file of the leading pawn.
+    pairs: [[PairsData; 4]; 2],
+    map: usize,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().unwrap(),
+    ))
+}
+
+// Reads big-endian bits of compressed blocks, treating bytes past the end as zeros.
+fn read_be(data: &[u8], offset: usize, bytes: usize) -> u64 {
+    (0..bytes).fold(0, |value, index| {
+        value << 8 | *data.get(offset + index).unwrap_or(&0) as u64
+    })
+}
+
+// Reads the bytes of the file at the offset into the whole buffer.
+#[cfg(unix)]
+fn read_at(file: &File, offset: usize, buffer: &mut [u8]) -> Option<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buffer, offset as u64).ok()
+}
+
+// Reads the bytes of the file at the offset into the whole buffer.
+#[cfg(windows)]
+fn read_at(file: &File, mut offset: usize, mut buffer: &mut [u8]) -> Option<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buffer.is_empty() {
+        let read = file.seek_read(buffer, offset as u64).ok()?;
+
+        if read == 0 {
+            return None;
+        }
+
+        buffer = &mut buffer[read..];
+        offset += read;
+    }
+
+    Some(())
+}
+
+// Reads the file into the header until it holds the bytes before `end`.
+fn extend_header(file: &File, header: &mut Vec<u8>, end: usize) -> Option<()> {
+    let start = header.len();
+
+    if end > start {
+        header.resize(end, 0);
+        read_at(file, start, &mut header[start..])?;
+    }
+
+    Some(())
+}
+
+impl Table {
+    /// Reads the headers of a table file, returning `None` if they aren't valid for the pieces of
+    /// the table.
+    pub fn new(file: File, kind: TableKind, info: TableInfo) -> Option<Self> {
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        };
+
+        let mut header = vec![];
+        extend_header(&file, &mut header, 5)?;
+
+        if header[..4] != magic {
+            return None;
+        }
+
+        let mut table = Self {
+            file,
+            header,
+            kind,
+            info,
+            pairs: Default::default(),
+            map: 0,
+        };
+
+        table.set_up()?;
+        Some(table)
+    }
+
+    fn sides(&self) -> usize {
+        if self.kind == TableKind::Wdl && !self.info.symmetric {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn files(&self) -> usize {
+        if self.info.has_pawns {
+            4
+        } else {
+            1
+        }
+    }
+
+    fn pairs(&self, side: usize, file: usize) -> &PairsData {
+        &self.pairs[side % self.sides()][if self.info.has_pawns { file } else { 0 }]
+    }
+
+    // Reads the headers of the table, locating the data of each of its parts.
+    fn set_up(&mut self) -> Option<()> {
+        let sides = self.sides();
+        let files = self.files();
+        let data = &mut self.header;
+        let info = &self.info;
+        let mut offset = 4;
+
+        let header = data[offset];
+        offset += 1;
+
+        if (header & HEADER_HAS_PAWNS != 0) != info.has_pawns
+            || (header & HEADER_SPLIT != 0) == info.symmetric
+        {
+            return None;
+        }
+
+        let both_pawns = info.has_pawns && info.pawn_counts[1] != 0;
+        let mut pairs: [[PairsData; 4]; 2] = Default::default();
+
+        for file in 0..files {
+            extend_header(&self.file, data, offset + 2 + info.piece_count)?;
+
+            let first = data[offset];
+            let second = if both_pawns { data[offset + 1] } else { 0xff };
+            let orders = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+            offset += 1 + both_pawns as usize;
+
+            for index in 0..info.piece_count {
+                let byte = data[offset];
+                offset += 1;
+
+                for (side, pairs) in pairs.iter_mut().enumerate().take(sides) {
+                    pairs[file].pieces[index] = if side == 0 { byte & 0xf } else { byte >> 4 };
+                }
+            }
+
+            for side in 0..sides {
+                set_groups(info, &mut pairs[side][file], orders[side], file)?;
+            }
+        }
+
+        offset += offset & 1;
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut().take(sides) {
+                offset = set_sizes(&mut side_pairs[file], &self.file, data, offset)?;
+            }
+        }
+
+        if self.kind == TableKind::Dtz {
+            self.map = offset;
+
+            for file_pairs in pairs[0].iter_mut().take(files) {
+                if file_pairs.flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+
+                if file_pairs.flags & FLAG_WIDE != 0 {
+                    offset += offset & 1;
+
+                    for index in 0..4 {
+                        extend_header(&self.file, data, offset + 2)?;
+                        file_pairs.map_index[index] = (offset - self.map) / 2 + 1;
+                        offset += 2 * read_u16(data, offset)? as usize + 2;
+                    }
+                } else {
+                    for index in 0..4 {
+                        extend_header(&self.file, data, offset + 1)?;
+                        file_pairs.map_index[index] = offset - self.map + 1;
+                        offset += data[offset] as usize + 1;
+                    }
+                }
+            }
+
+            // The map is read while probing, so it is kept in the header.
+            extend_header(&self.file, data, offset)?;
+            offset += offset & 1;
+        }
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut().take(sides) {
+                side_pairs[file].sparse_index = offset;
+                offset += side_pairs[file].sparse_index_size * 6;
+            }
+        }
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut().take(sides) {
+                side_pairs[file].block_lengths = offset;
+                offset += side_pairs[file].block_lengths_size * 2;
+            }
+        }
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut().take(sides) {
+                offset = (offset + 0x3f) & !0x3f;
+                side_pairs[file].data = offset;
+                offset = side_pairs[file]
+                    .num_blocks
+                    .checked_mul(side_pairs[file].block_size)?
+                    .checked_add(offset)?;
+            }
+        }
+
+        if offset as u64 > self.file.metadata().ok()?.len() {
+            return None;
+        }
+
+        self.pairs = pairs;
+        Some(())
+    }
+
+    /// Checks if the DTZ table stores the passed side to move, as DTZ tables only store one of
+    /// them unless both sides have the same pieces and there are no pawns.
+    pub fn stores_side(&self, side: usize, file: usize) -> bool {
+        self.kind == TableKind::Wdl
+            || (self.pairs(side, file).flags & FLAG_STM) as usize == side
+            || self.info.symmetric && !self.info.has_pawns
+    }
+
+    /// Returns the pieces of the part of the table, in the order they are encoded. Each piece is
+    /// its type, from `1` for pawns to `6` for kings, with `8` added for black pieces.
+    pub fn pieces(&self, side: usize, file: usize) -> &[u8] {
+        &self.pairs(side, file).pieces[..self.info.piece_count]
+    }
+
+    /// Returns the value of the position with the passed squares and pieces, which are already
+    /// ordered and mirrored as the table expects, except for the symmetries computed here.
+    ///
+    /// For WDL tables, the value ranges from `-2` for a loss to `2` for a win. For DTZ tables,
+    /// it is the distance to zeroing in plies for the passed WDL value.
+    pub fn probe(&self, side: usize, file: usize, squares: &mut [u8], wdl: i8) -> Option<i32> {
+        let pairs = self.pairs(side, file);
+        let index = position_index(&self.info, pairs, squares)?;
+        let value = self.decompress(pairs, index)? as i32;
+
+        Some(match self.kind {
+            TableKind::Wdl => value - 2,
+            TableKind::Dtz => self.map_dtz(file, value, wdl)?,
+        })
+    }
+
+    // Converts a stored DTZ value to plies, undoing the remapping of values by frequency.
+    fn map_dtz(&self, file: usize, value: i32, wdl: i8) -> Option<i32> {
+        let pairs = self.pairs(0, file);
+        let mut value = value as usize;
+
+        if pairs.flags & FLAG_MAPPED != 0 {
+            let map_index = pairs.map_index[match wdl {
+                2 => 0,
+                -2 => 1,
+                1 => 2,
+                _ => 3,
+            }];
+
+            value = if pairs.flags & FLAG_WIDE != 0 {
+                read_u16(&self.header, self.map + 2 * (map_index + value))? as usize
+            } else {
+                *self.header.get(self.map + map_index + value)? as usize
+            };
+        }
+
+        let in_moves = match wdl {
+            2 => pairs.flags & FLAG_WIN_PLIES == 0,
+            -2 => pairs.flags & FLAG_LOSS_PLIES == 0,
+            _ => true,
+        };
+
+        Some(if in_moves { value * 2 } else { value } as i32 + 1)
+    }
+
+    // Decompresses the value at the passed index of the part of the table.
+    fn decompress(&self, pairs: &PairsData, index: u64) -> Option<u16> {
+        if pairs.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(pairs.min_sym_len as u16);
+        }
+
+        let span = pairs.span as u64;
+
+        // Each entry of the sparse index points to the block and the offset in it of the value
+        // in the middle of every span of values.
+        let mut entry = [0; 6];
+        read_at(
+            &self.file,
+            pairs.sparse_index + 6 * (index / span) as usize,
+            &mut entry,
+        )?;
+        let mut block = read_u32(&entry, 0)? as usize;
+        let mut offset = read_u16(&entry, 4)? as i64;
+
+        offset += (index % span) as i64 - (span / 2) as i64;
+
+        let block_length = |block: usize| {
+            let mut length = [0; 2];
+            read_at(&self.file, pairs.block_lengths + 2 * block, &mut length)?;
+            Some(u16::from_le_bytes(length) as i64)
+        };
+
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        if block >= pairs.num_blocks {
+            return None;
+        }
+
+        let mut data = vec![0; pairs.block_size];
+        read_at(&self.file, pairs.data + block * pairs.block_size, &mut data)?;
+
+        let mut pointer = 0;
+        let mut buffer = read_be(&data, pointer, 8);
+        let mut buffer_size = 64;
+        pointer += 8;
+
+        let lowest_sym = |length: usize| read_u16(&self.header, pairs.lowest_sym + 2 * length);
+        let symlen = |symbol: u16| pairs.symlen.get(symbol as usize).map(|&len| len as i64);
+
+        // Symbols are read until reaching the one which expands into the searched value.
+        let mut symbol = loop {
+            let mut length = 0;
+
+            while buffer < *pairs.base64.get(length)? {
+                length += 1;
+            }
+
+            let shift = 64 - length - pairs.min_sym_len as usize;
+            let symbol = ((buffer - pairs.base64[length]) >> shift) as u16 + lowest_sym(length)?;
+
+            if offset < symlen(symbol)? + 1 {
+                break symbol;
+            }
+
+            offset -= symlen(symbol)? + 1;
+            length += pairs.min_sym_len as usize;
+            buffer <<= length;
+            buffer_size -= length;
+
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= read_be(&data, pointer, 4) << (64 - buffer_size);
+                pointer += 4;
+            }
+        };
+
+        // The symbol is then expanded into the pair it replaced, until reaching a single value.
+        while symlen(symbol)? != 0 {
+            let (left, right) = self.children(pairs, symbol)?;
+
+            if offset < symlen(left)? + 1 {
+                symbol = left;
+            } else {
+                offset -= symlen(left)? + 1;
+                symbol = right;
+            }
+        }
+
+        Some(self.children(pairs, symbol)?.0)
+    }
+
+    // Returns the left and right symbols a symbol expands to. Symbols which don't expand store
+    // their value as the left symbol, and `0xfff` as the right one.
+    fn children(&self, pairs: &PairsData, symbol: u16) -> Option<(u16, u16)> {
+        children(&self.header, pairs.btree, symbol)
+    }
+}
+
+// Returns the index of the position with the passed squares in a part of a table, which are
+// ordered and mirrored as in `Table::probe`.
+fn position_index(info: &TableInfo, pairs: &PairsData, squares: &mut [u8]) -> Option<u64> {
+    let maps = maps();
+    let lead_pawns = if info.has_pawns {
+        pairs.group_len[0]
+    } else {
+        0
+    };
+
+    if squares[0] & 7 > 3 {
+        for square in squares.iter_mut() {
+            *square ^= 7;
+        }
+    }
+
+    let mut index = if info.has_pawns {
+        let mut index = maps.lead_pawn_index[lead_pawns][squares[0] as usize];
+
+        squares[1..lead_pawns].sort_by_key(|&square| maps.pawns[square as usize]);
+
+        for (count, &square) in squares[1..lead_pawns].iter().enumerate() {
+            index += maps.binomial[count + 1][maps.pawns[square as usize] as usize];
+        }
+
+        index
+    } else {
+        if squares[0] >> 3 > 3 {
+            for square in squares.iter_mut() {
+                *square ^= 56;
+            }
+        }
+
+        // The first piece of the leading group off the diagonal is mapped below it.
+        if let Some(first) =
+            (0..pairs.group_len[0]).find(|&index| off_diagonal(squares[index]) != 0)
+        {
+            if off_diagonal(squares[first]) > 0 {
+                for square in &mut squares[first..] {
+                    *square = (*square >> 3 | *square << 3) & 63;
+                }
+            }
+        }
+
+        leading_pieces_index(info, squares)
+    };
+
+    index *= pairs.group_index[0];
+
+    let mut group_start = pairs.group_len[0];
+    let mut remaining_pawns = info.has_pawns && info.pawn_counts[1] != 0;
+
+    for group in 1.. {
+        let group_len = pairs.group_len[group];
+
+        if group_len == 0 {
+            break;
+        }
+
+        squares[group_start..group_start + group_len].sort_unstable();
+
+        let mut group_index = 0;
+
+        for offset in 0..group_len {
+            let square = squares[group_start + offset];
+            let adjust = squares[..group_start]
+                .iter()
+                .filter(|&&other| square > other)
+                .count();
+            let mapped = (square as usize).checked_sub(adjust + 8 * remaining_pawns as usize)?;
+
+            group_index += maps.binomial[offset + 1][mapped];
+        }
+
+        remaining_pawns = false;
+        index += group_index * pairs.group_index[group];
+        group_start += group_len;
+    }
+
+    Some(index)
+}
+
+fn children(data: &[u8], btree: usize, symbol: u16) -> Option<(u16, u16)> {
+    let offset = btree + 3 * symbol as usize;
+    let bytes = data.get(offset..offset + 3)?;
+
+    Some((
+        (bytes[1] as u16 & 0xf) << 8 | bytes[0] as u16,
+        (bytes[2] as u16) << 4 | (bytes[1] as u16) >> 4,
+    ))
+}
+
+// Returns the index of the leading group of a table without pawns, which is either made of the
+// kings alone, or of three unique pieces.
+fn leading_pieces_index(info: &TableInfo, squares: &[u8]) -> u64 {
+    let maps = maps();
+
+    if !info.has_unique_pieces {
+        return maps.kk[maps.a1d1d4[squares[0] as usize] as usize][squares[1] as usize] as u64;
+    }
+
+    let adjust1 = (squares[1] > squares[0]) as u64;
+    let adjust2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+    let [first, second, third] = [squares[0], squares[1], squares[2]].map(u64::from);
+    let rank = |square: u64| square >> 3;
+
+    if off_diagonal(squares[0]) != 0 {
+        (maps.a1d1d4[squares[0] as usize] as u64 * 63 + second - adjust1) * 62 + third - adjust2
+    } else if off_diagonal(squares[1]) != 0 {
+        (6 * 63 + rank(first) * 28 + maps.b1h1h7[squares[1] as usize] as u64) * 62 + third - adjust2
+    } else if off_diagonal(squares[2]) != 0 {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + rank(first) * 7 * 28
+            + (rank(second) - adjust1) * 28
+            + maps.b1h1h7[squares[2] as usize] as u64
+    } else {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + 4 * 7 * 28
+            + rank(first) * 7 * 6
+            + (rank(second) - adjust1) * 6
+            + (rank(third) - adjust2)
+    }
+}
+
+// Splits the pieces of a part of a table into the groups which are encoded together, and
+// computes the index each group starts at from the order the groups are encoded in.
+fn set_groups(info: &TableInfo, pairs: &mut PairsData, order: [u8; 2], file: usize) -> Option<()> {
+    let maps = maps();
+    let mut groups = 0;
+    let mut first_len: i32 = if info.has_pawns {
+        0
+    } else if info.has_unique_pieces {
+        3
+    } else {
+        2
+    };
+
+    pairs.group_len[0] = 1;
+
+    for index in 1..info.piece_count {
+        first_len -= 1;
+
+        if first_len > 0 || pairs.pieces[index] == pairs.pieces[index - 1] {
+            pairs.group_len[groups] += 1;
+        } else {
+            groups += 1;
+            pairs.group_len[groups] = 1;
+        }
+    }
+
+    groups += 1;
+    pairs.group_len[groups] = 0;
+
+    let both_pawns = info.has_pawns && info.pawn_counts[1] != 0;
+    let mut next = if both_pawns { 2 } else { 1 };
+    let mut free_squares =
+        64 - pairs.group_len[0] - if both_pawns { pairs.group_len[1] } else { 0 };
+    let mut index = 1u64;
+    let mut position = 0;
+
+    while next < groups || position == order[0] || position == order[1] {
+        if position == order[0] {
+            pairs.group_index[0] = index;
+            index *= if info.has_pawns {
+                *maps.lead_pawns_size.get(pairs.group_len[0])?.get(file)?
+            } else if info.has_unique_pieces {
+                31332
+            } else {
+                462
+            };
+        } else if position == order[1] {
+            pairs.group_index[1] = index;
+            index *= maps.binomial.get(pairs.group_len[1])?[48 - pairs.group_len[0]];
+        } else {
+            pairs.group_index[next] = index;
+            index *= maps.binomial.get(pairs.group_len[next])?[free_squares];
+            free_squares -= pairs.group_len[next];
+            next += 1;
+        }
+
+        position += 1;
+
+        if position > 0xf {
+            return None;
+        }
+    }
+
+    pairs.group_index[groups] = index;
+    Some(())
+}
+
+// Reads the sizes and the Huffman code of a part of a table into the header, returning the
+// offset after them.
+fn set_sizes(
+    pairs: &mut PairsData,
+    file: &File,
+    header: &mut Vec<u8>,
+    mut offset: usize,
+) -> Option<usize> {
+    extend_header(file, header, offset + 2)?;
+    pairs.flags = header[offset];
+    offset += 1;
+
+    if pairs.flags & FLAG_SINGLE_VALUE != 0 {
+        pairs.min_sym_len = header[offset];
+        return Some(offset + 1);
+    }
+
+    extend_header(file, header, offset + 9)?;
+    let data = &*header;
+
+    let groups = pairs.group_len.iter().position(|&len| len == 0)?;
+    let size = pairs.group_index[groups];
+
+    pairs.block_size = 1usize.checked_shl(*data.get(offset)? as u32)?;
+    pairs.span = 1usize.checked_shl(*data.get(offset + 1)? as u32)?;
+    pairs.sparse_index_size = size.div_ceil(pairs.span as u64) as usize;
+    let padding = *data.get(offset + 2)? as usize;
+    pairs.num_blocks = read_u32(data, offset + 3)? as usize;
+    pairs.block_lengths_size = pairs.num_blocks + padding;
+    let max_sym_len = *data.get(offset + 7)?;
+    pairs.min_sym_len = *data.get(offset + 8)?;
+    offset += 9;
+
+    pairs.lowest_sym = offset;
+
+    let lengths = (max_sym_len as usize + 1).checked_sub(pairs.min_sym_len as usize)?;
+    extend_header(file, header, offset + lengths * 2 + 2)?;
+    let data = &*header;
+    let lowest_sym = |length: usize| Some(read_u16(data, offset + 2 * length)? as u64);
+
+    // The canonical Huffman code orders longer symbols first, so the lowest symbol of each
+    // length left-aligned in 64 bits decreases with the length.
+    pairs.base64 = vec![0; lengths];
+
+    for length in (0..lengths.saturating_sub(1)).rev() {
+        pairs.base64[length] = (pairs.base64[length + 1]
+            .wrapping_add(lowest_sym(length)?)
+            .wrapping_sub(lowest_sym(length + 1)?))
+            / 2;
+    }
+
+    for (length, base) in pairs.base64.iter_mut().enumerate() {
+        *base = base
+            .checked_shl((64 - length - pairs.min_sym_len as usize) as u32)
+            .unwrap_or(0);
+    }
+
+    offset += lengths * 2;
+
+    let symbols = read_u16(data, offset)? as usize;
+    offset += 2;
+    pairs.btree = offset;
+
+    extend_header(file, header, offset + symbols * 3)?;
+    let data = &*header;
+
+    // Symbols stand for pairs of other symbols, which are expanded to count their values.
+    let mut symlen = vec![None; symbols];
+
+    for symbol in 0..symbols {
+        set_symlen(data, pairs.btree, symbol as u16, &mut symlen)?;
+    }
+
+    pairs.symlen = symlen.into_iter().collect::<Option<_>>()?;
+
+    Some(offset + symbols * 3 + (symbols & 1))
+}
+
+// Computes the number of values minus one represented by the symbol and the ones it expands to.
+fn set_symlen(data: &[u8], btree: usize, symbol: u16, symlen: &mut [Option<u8>]) -> Option<u8> {
+    if let Some(len) = *symlen.get(symbol as usize)? {
+        return Some(len);
+    }
+
+    let (left, right) = children(data, btree, symbol)?;
+
+    let len = if right == 0xfff {
+        0
+    } else {
+        let left = set_symlen(data, btree, left, symlen)?;
+        let right = set_symlen(data, btree, right, symlen)?;
+
+        left.wrapping_add(right).wrapping_add(1)
+    };
+
+    symlen[symbol as usize] = Some(len);
+    Some(len)
+}
@@ -0,0 +1,123 @@
+use std::io::{self, Read, Write};
+
+use mangrove_core::material::{MaterialSignature, ParseMaterialSignatureError};
+
+use crate::{
+    position::{Indexer, Position},
+    MAX_PIECES,
+};
+
+/// The bytes every table file starts with.
+const MAGIC: [u8; 4] = *b"MGTB";
+
+/// The version of the table file format, stored after the magic bytes.
+const VERSION: u8 = 2;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ReadTableError {
+    #[error("failed to read table")]
+    Io(#[source] io::Error),
+    #[error("file isn't a table")]
+    InvalidMagic,
+    #[error("unsupported table version {0}")]
+    UnsupportedVersion(u8),
+    #[error("invalid table material")]
+    InvalidMaterial(#[source] ParseMaterialSignatureError),
+    #[error("table material has more than {MAX_PIECES} pieces other than the kings")]
+    TooManyPieces,
+    #[error("table length doesn't match its material")]
+    InvalidLength,
+}
+
+/// The solved positions of a single material, with white to move and with black to move.
+///
+/// Each position is stored as a byte, which is `0` if it's drawn, and otherwise one more than the
+/// number of plies until mate with best play, which is even when the side to move gets mated and
+/// odd when it mates.
+///
+/// The file format is made of the magic bytes `MGTB`, a version byte, the length of the material
+/// name as a byte followed by the name (such as `KBNvK`), the number of positions as a 32-bit
+/// little-endian integer, and the byte of each position in the order of its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    material: MaterialSignature,
+    indexer: Indexer,
+    values: Vec<u8>,
+}
+
+impl Table {
+    pub(crate) fn new(material: MaterialSignature, indexer: Indexer, values: Vec<u8>) -> Self {
+        Self {
+            material,
+            indexer,
+            values,
+        }
+    }
+
+    pub fn material(&self) -> &MaterialSignature {
+        &self.material
+    }
+
+    /// Returns the number of plies of the longest forced mate in the table, counted from a
+    /// position with the winning side to move, or `None` if there are no won positions.
+    pub fn longest_mate(&self) -> Option<u8> {
+        self.values
+            .iter()
+            .filter(|&&value| value != 0 && value % 2 == 0)
+            .map(|&value| value - 1)
+            .max()
+    }
+
+    pub(crate) fn value(&self, position: &Position) -> u8 {
+        self.values[self.indexer.index(position)]
+    }
+
+    /// Writes the table in its file format.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let name = self.material.to_string();
+
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION, name.len() as u8])?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(&(self.values.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.values)
+    }
+
+    /// Reads a table in its file format.
+    pub fn read(mut reader: impl Read) -> Result<Self, ReadTableError> {
+        let mut header = [0; 6];
+        reader.read_exact(&mut header).map_err(ReadTableError::Io)?;
+
+        if header[..4] != MAGIC {
+            return Err(ReadTableError::InvalidMagic);
+        } else if header[4] != VERSION {
+            return Err(ReadTableError::UnsupportedVersion(header[4]));
+        }
+
+        let mut name = vec![0; header[5] as usize];
+        reader.read_exact(&mut name).map_err(ReadTableError::Io)?;
+
+        let material = String::from_utf8_lossy(&name)
+            .parse::<MaterialSignature>()
+            .map_err(ReadTableError::InvalidMaterial)?;
+
+        if material.piece_count() > MAX_PIECES {
+            return Err(ReadTableError::TooManyPieces);
+        }
+
+        let indexer = Indexer::new(&material);
+
+        let mut length = [0; 4];
+        reader.read_exact(&mut length).map_err(ReadTableError::Io)?;
+
+        if u32::from_le_bytes(length) as usize != indexer.size() {
+            return Err(ReadTableError::InvalidLength);
+        }
+
+        let mut values = vec![0; indexer.size()];
+        reader.read_exact(&mut values).map_err(ReadTableError::Io)?;
+
+        Ok(Self::new(material, indexer, values))
+    }
+}
@@ -74,18 +74,19 @@ pub fn gen_game<B: Backend>(
 
         positions.push((boards.to_vec(), move_probabilities));
 
-        if positions.len() >= ply_cap {
-            break Outcome::Draw;
-        } else if let Some(outcome) = game.outcome() {
-            break outcome;
+        if let Some(outcome) = game.outcome() {
+            break Some(outcome);
+        } else if positions.len() >= ply_cap {
+            break None;
         }
     };
 
     let finishing_color = game.board().playing_color;
 
+    // Games reaching the ply cap are adjudicated as draws
     let outcome_value = match outcome {
-        Outcome::Win(_) => 1.0,
-        Outcome::Draw => 0.0,
+        Some(Outcome::Win(_)) => 1.0,
+        Some(Outcome::Draw(_)) | None => 0.0,
     };
 
     // TODO: Consider splitting on the outcome in this section, or maybe splitting the boards into
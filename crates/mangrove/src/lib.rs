@@ -1,12 +1,13 @@
 mod engine;
 
-use std::{error::Error, fs::File, io, path::PathBuf};
+use std::{error::Error, fs::File, io, path::PathBuf, str::FromStr};
 
 use clap::{
     builder::{styling::AnsiColor, Styles},
     Parser, Subcommand,
 };
 use engine::{Engine, EngineParameters, MessageReader};
+use mangrove_core::board::Board;
 use tracing::Level;
 
 fn styles() -> Styles {
@@ -58,6 +59,40 @@ enum Command {
         )]
         exploration_rate: f32,
     },
+    #[command(
+        about = "Count the leaf nodes of the move tree of a position, for debugging move generation"
+    )]
+    Perft {
+        #[arg(help = "The position to count the nodes of, in FEN.")]
+        fen: String,
+        #[arg(help = "The depth of the move tree to count the leaf nodes of.")]
+        depth: u32,
+        #[arg(
+            short = 'd',
+            long,
+            help = "Print the number of leaf nodes reached by each legal move of the position."
+        )]
+        divide: bool,
+        #[arg(
+            long,
+            conflicts_with = "divide",
+            help = "Print the number of captures, en passants, castles, promotions, checks, discovered checks, double checks and checkmates leading to the leaf nodes. This doesn't use multiple threads or caching."
+        )]
+        detailed: bool,
+        #[arg(
+            short = 't',
+            long,
+            help = "The number of threads to count with, when not dividing or printing details.",
+            default_value_t = 1
+        )]
+        threads: usize,
+        #[arg(
+            long,
+            help = "The size of the node count cache in MiB, when not dividing or printing details. Shallow counts only use as much of it as they can fill.",
+            default_value_t = 64
+        )]
+        hash: usize,
+    },
 }
 
 fn initialize_tracing(trace_file: PathBuf, tracing_level: Level) -> Result<(), Box<dyn Error>> {
@@ -82,6 +117,64 @@ fn run(search_threads: usize, exploration_rate: f32) -> Result<(), Box<dyn Error
     .run()
 }
 
+// The size of an entry of the node count cache used by the `perft` command, in bytes
+const PERFT_CACHE_ENTRY_SIZE: usize = 16;
+
+// Returns the number of entries of the node count cache for a count of the passed depth, using at
+// most the passed size in MiB. Shallow counts reach too few positions to fill a large cache, so
+// they are given a smaller one instead of paying for its allocation.
+fn perft_cache_entries(depth: u32, hash: usize) -> usize {
+    let reachable = 1usize
+        .checked_shl(depth.saturating_mul(4))
+        .unwrap_or(usize::MAX);
+
+    (hash.saturating_mul(1 << 20) / PERFT_CACHE_ENTRY_SIZE).min(reachable)
+}
+
+fn perft(
+    fen: &str,
+    depth: u32,
+    divide: bool,
+    detailed: bool,
+    threads: usize,
+    hash: usize,
+) -> Result<(), Box<dyn Error>> {
+    let board = Board::from_str(fen)?;
+
+    if divide {
+        let divided = board.perft_divide(depth);
+
+        for (chess_move, nodes) in &divided {
+            println!("{chess_move}: {nodes}");
+        }
+
+        println!();
+        println!(
+            "Nodes: {}",
+            divided.iter().map(|(_, nodes)| nodes).sum::<u64>()
+        );
+    } else if detailed {
+        let stats = board.perft_detailed(depth);
+
+        println!("Nodes: {}", stats.nodes);
+        println!("Captures: {}", stats.captures);
+        println!("En passants: {}", stats.en_passants);
+        println!("Castles: {}", stats.castles);
+        println!("Promotions: {}", stats.promotions);
+        println!("Checks: {}", stats.checks);
+        println!("Discovered checks: {}", stats.discovered_checks);
+        println!("Double checks: {}", stats.double_checks);
+        println!("Checkmates: {}", stats.checkmates);
+    } else {
+        println!(
+            "Nodes: {}",
+            board.perft_parallel(depth, threads, perft_cache_entries(depth, hash))
+        );
+    }
+
+    Ok(())
+}
+
 pub fn cli() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
@@ -94,5 +187,13 @@ pub fn cli() -> Result<(), Box<dyn Error>> {
             search_threads,
             exploration_rate,
         } => run(search_threads, exploration_rate),
+        Command::Perft {
+            fen,
+            depth,
+            divide,
+            detailed,
+            threads,
+            hash,
+        } => perft(&fen, depth, divide, detailed, threads, hash),
     }
 }